@@ -0,0 +1,348 @@
+//! A reference to the plants, listing where each can grow and how it spreads.
+//! Plants stay hidden until they've been seen in a level.
+
+use std::collections::BTreeSet;
+
+use belly::{core::ess::Styles, prelude::*};
+use bevy::prelude::*;
+use leafwing_input_manager::{plugin::InputManagerSystem, prelude::*, Actionlike};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::GameAssets,
+    clock::SimulationClock,
+    control::{Action, Player},
+    locale::Strings,
+    states::AppState,
+    storage,
+    tile::{Plant, PlantDefinition, PlantDefinitions},
+};
+
+pub struct AlmanacPlugin;
+
+impl Plugin for AlmanacPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<Almanac>(ALMANAC_KEY).unwrap_or_default())
+            .init_resource::<AlmanacOpen>()
+            .init_resource::<PausedBeforeAlmanac>()
+            .add_event::<ToggleAlmanac>()
+            .add_event::<CloseAlmanac>()
+            .add_system(discover_plants.in_set(OnUpdate(AppState::InGame)))
+            .add_system(toggle_almanac.in_set(OnUpdate(AppState::InGame)))
+            .add_system(close_almanac.in_set(OnUpdate(AppState::InGame)))
+            .add_system(close_almanac.in_set(OnUpdate(AppState::Almanac)))
+            .add_system(
+                hold_player
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputManagerSystem::Update)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_system(
+                setup_screen
+                    .in_base_set(CoreSet::PreUpdate)
+                    .run_if(in_state(AppState::Almanac)),
+            )
+            .add_system(
+                setup_overlay
+                    .in_base_set(CoreSet::PreUpdate)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_system(clear_menu.in_schedule(OnExit(AppState::Almanac)))
+            .add_system(clear_menu.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+const ALMANAC_KEY: &str = "almanac";
+
+/// The plants that have been seen so far, persisted between sessions.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Almanac {
+    pub discovered: BTreeSet<String>,
+}
+
+impl Almanac {
+    pub fn is_discovered(&self, id: &str) -> bool {
+        self.discovered.contains(id)
+    }
+
+    pub fn save(&self) {
+        storage::save(ALMANAC_KEY, self);
+    }
+}
+
+/// Whether the almanac is shown over the level being played.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AlmanacOpen(pub bool);
+
+/// Whether the clock was already paused when the almanac was opened over the level,
+/// so closing it leaves the clock as it was.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+struct PausedBeforeAlmanac(bool);
+
+/// Opens or closes the almanac over the level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToggleAlmanac;
+
+/// Sent by the almanac's close button, wherever it was opened from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CloseAlmanac;
+
+fn discover_plants(plants: Query<&Plant, Changed<Plant>>, mut almanac: ResMut<Almanac>) {
+    let new = plants
+        .iter()
+        .filter_map(|plant| match plant {
+            Plant::Plant(id) if !almanac.is_discovered(id) => Some(id.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if new.is_empty() {
+        return;
+    }
+    almanac.discovered.extend(new);
+    almanac.save();
+}
+
+fn toggle_almanac(
+    mut events: EventReader<ToggleAlmanac>,
+    mut open: ResMut<AlmanacOpen>,
+    mut clock: ResMut<SimulationClock>,
+    mut paused_before: ResMut<PausedBeforeAlmanac>,
+) {
+    for _ in events.iter() {
+        let value = !open.0;
+        set_open(value, &mut open, &mut clock, &mut paused_before);
+    }
+}
+
+fn close_almanac(
+    mut events: EventReader<CloseAlmanac>,
+    mut open: ResMut<AlmanacOpen>,
+    mut clock: ResMut<SimulationClock>,
+    mut paused_before: ResMut<PausedBeforeAlmanac>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+    if state.0 == AppState::Almanac {
+        next_state.set(AppState::Menu);
+    } else if open.0 {
+        set_open(false, &mut open, &mut clock, &mut paused_before);
+    }
+}
+
+/// The level waits while the almanac is being read, then carries on as it was.
+fn set_open(
+    value: bool,
+    open: &mut AlmanacOpen,
+    clock: &mut SimulationClock,
+    paused_before: &mut PausedBeforeAlmanac,
+) {
+    if value == open.0 {
+        return;
+    }
+    open.0 = value;
+    if value {
+        paused_before.0 = clock.paused;
+        clock.paused = true;
+    } else {
+        clock.paused = paused_before.0;
+    }
+}
+
+/// The board is hidden under the almanac, so the player's keys and buttons do nothing until it closes.
+fn hold_player(open: Res<AlmanacOpen>, mut players: Query<&mut ActionState<Action>, With<Player>>) {
+    if !open.0 {
+        return;
+    }
+    for mut actions in players.iter_mut() {
+        for action in Action::variants() {
+            actions.release(action);
+        }
+    }
+}
+
+/// A plant's page in the almanac.
+struct Entry {
+    name: String,
+    image: Vec<String>,
+    lines: Vec<String>,
+}
+
+fn describe(
+    definition: &PlantDefinition,
+    plants: &PlantDefinitions,
+    strings: &Strings,
+) -> Vec<String> {
+    let mut lines = definition
+        .description_in(&plants.language)
+        .into_iter()
+        .collect::<Vec<_>>();
+    let tags = definition.tags_in(&plants.language);
+    if !tags.is_empty() {
+        lines.push(tags.join(", "));
+    }
+    lines.push(strings.format(
+        "almanac.grows_on",
+        &[("grounds", &definition.allowed_grounds.names(strings))],
+    ));
+    if !definition.required_neighbour_grounds.0.is_empty() {
+        lines.push(strings.format(
+            "almanac.needs",
+            &[(
+                "needs",
+                &definition.required_neighbour_grounds.names(strings),
+            )],
+        ));
+    }
+    if !definition.required_neighbour_plants.is_empty() {
+        lines.push(strings.format(
+            "almanac.needs",
+            &[(
+                "needs",
+                &plants.names(&definition.required_neighbour_plants, strings),
+            )],
+        ));
+    }
+    lines.push(definition.spread.describe(plants, strings));
+    lines.push(match definition.aggressiveness {
+        0 => strings.get("almanac.passive"),
+        n => strings.format("almanac.aggressive", &[("aggressiveness", &n)]),
+    });
+    lines
+}
+
+fn entries(almanac: &Almanac, plants: &PlantDefinitions, strings: &Strings) -> Vec<Entry> {
+    let mut definitions = plants.definitions.iter().collect::<Vec<_>>();
+    definitions.sort_by_key(|definition| plants.name(&definition.id));
+    definitions
+        .into_iter()
+        .map(|definition| {
+            if almanac.is_discovered(&definition.id) {
+                Entry {
+                    name: plants.name(&definition.id),
+                    image: vec![definition.asset.clone()],
+                    lines: describe(definition, plants, strings),
+                }
+            } else {
+                Entry {
+                    name: "???".to_string(),
+                    image: vec![],
+                    lines: vec![strings.get("almanac.undiscovered")],
+                }
+            }
+        })
+        .collect()
+}
+
+#[derive(Component)]
+struct MenuItem;
+
+fn spawn_almanac(
+    commands: &mut Commands,
+    class: &str,
+    entries: Vec<Entry>,
+    strings: &Strings,
+) -> Entity {
+    let ui = commands.spawn(MenuItem).id();
+    let class = strings.root_class(class);
+    let title = strings.get("almanac.title");
+    let close = strings.get("common.close");
+    commands.add(eml! {
+        <body {ui} class={class}>
+            <div c:header>{title}</div>
+            <div c:almanac_entries>
+                <for entry in=entries>
+                    <div c:almanac_entry>
+                        <span c:almanac_name>{entry.name.clone()}</span>
+                        <for image in=entry.image.clone()>
+                            <img c:almanac_image src={image}></img>
+                        </for>
+                        <for line in=entry.lines.clone()>
+                            <span c:almanac_line>{line}</span>
+                        </for>
+                    </div>
+                </for>
+            </div>
+            <button c:menu_button c:small_menu_button on:press=|ctx| ctx.send_event(CloseAlmanac)>
+                <span c:content>
+                {close}
+                </span>
+            </button>
+        </body>
+    });
+    ui
+}
+
+fn setup_screen(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    almanac: Res<Almanac>,
+    plants: Res<PlantDefinitions>,
+    strings: Res<Strings>,
+    query: Query<Entity, With<MenuItem>>,
+) {
+    if !query.is_empty() && !almanac.is_changed() && !strings.is_changed() {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    styles.insert(assets.ui_style.clone());
+    spawn_almanac(
+        &mut commands,
+        "root",
+        entries(&almanac, &plants, &strings),
+        &strings,
+    );
+}
+
+fn setup_overlay(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    almanac: Res<Almanac>,
+    open: Res<AlmanacOpen>,
+    plants: Res<PlantDefinitions>,
+    strings: Res<Strings>,
+    query: Query<Entity, With<MenuItem>>,
+) {
+    if !open.is_changed() && !(open.0 && almanac.is_changed()) {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !open.0 {
+        return;
+    }
+
+    styles.insert(assets.ui_style.clone());
+    let ui = spawn_almanac(
+        &mut commands,
+        "almanac_overlay",
+        entries(&almanac, &plants, &strings),
+        &strings,
+    );
+    // Drawn over the level's own UI, which is rebuilt independently.
+    commands.entity(ui).insert(ZIndex::Global(10));
+}
+
+fn clear_menu(
+    mut commands: Commands,
+    query: Query<Entity, With<MenuItem>>,
+    mut open: ResMut<AlmanacOpen>,
+    mut clock: ResMut<SimulationClock>,
+    mut paused_before: ResMut<PausedBeforeAlmanac>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    set_open(false, &mut open, &mut clock, &mut paused_before);
+}
@@ -0,0 +1,102 @@
+//! Short tweens that make tile changes easier to follow - plants growing in and wilting away,
+//! flickering fire, and old ground fading out under the new one.
+
+use bevy::prelude::*;
+
+use crate::states::AppState;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(animate_tweens.in_set(OnUpdate(AppState::InGame)))
+            .add_system(animate_tweens.in_set(OnUpdate(AppState::Editor)))
+            .add_system(flicker.in_set(OnUpdate(AppState::InGame)))
+            .add_system(flicker.in_set(OnUpdate(AppState::Editor)));
+    }
+}
+
+/// How long a tween takes to play out.
+pub const TWEEN_SECONDS: f32 = 0.3;
+
+const FLICKER_SPEED: f32 = 13.;
+const FLICKER_SCALE: f32 = 0.06;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TweenKind {
+    /// Scales a newly grown plant up from nothing.
+    Grow,
+    /// Shrinks and fades a plant that died, then hides it.
+    Wilt,
+    /// Fades out a temporary sprite, like the old ground under a crossfade, then despawns it.
+    FadeOut,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Tween {
+    pub kind: TweenKind,
+    elapsed: f32,
+}
+
+impl Tween {
+    pub fn new(kind: TweenKind) -> Self {
+        Self { kind, elapsed: 0. }
+    }
+}
+
+/// Makes a burning tile's sprite waver in size.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Flicker;
+
+fn animate_tweens(
+    mut commands: Commands,
+    mut tweens: Query<(
+        Entity,
+        &mut Tween,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+    time: Res<Time>,
+) {
+    for (entity, mut tween, mut transform, mut sprite, mut visibility) in tweens.iter_mut() {
+        tween.elapsed += time.delta_seconds();
+        let progress = (tween.elapsed / TWEEN_SECONDS).clamp(0., 1.);
+        let done = progress >= 1.;
+
+        match tween.kind {
+            TweenKind::Grow => {
+                // Ease out, so growth starts quickly and settles gently.
+                let eased = 1. - (1. - progress).powi(3);
+                transform.scale = Vec3::splat(eased);
+                if done {
+                    commands.entity(entity).remove::<Tween>();
+                }
+            }
+            TweenKind::Wilt => {
+                transform.scale = Vec3::splat(1. - progress * 0.5);
+                sprite.color.set_a(1. - progress);
+                if done {
+                    *visibility = Visibility::Hidden;
+                    transform.scale = Vec3::ONE;
+                    commands.entity(entity).remove::<Tween>();
+                }
+            }
+            TweenKind::FadeOut => {
+                sprite.color.set_a(1. - progress);
+                if done {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+    }
+}
+
+fn flicker(mut fires: Query<(Entity, &mut Transform), With<Flicker>>, time: Res<Time>) {
+    for (entity, mut transform) in fires.iter_mut() {
+        // Offset each fire a little so neighbouring tiles don't pulse in step.
+        let phase = entity.index() as f32;
+        let wave = (time.elapsed_seconds() * FLICKER_SPEED + phase).sin();
+        transform.scale = Vec3::splat(1. + wave * FLICKER_SCALE);
+    }
+}
@@ -5,13 +5,16 @@ use bevy_asset_loader::prelude::*;
 
 use crate::{
     level_asset::LevelList,
+    locale::Strings,
     tile::{PlantDefinitions, PlantDefinitionsAsset},
 };
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct GameAssets {
     #[asset(path = "font.ttf")]
     pub font: Handle<Font>,
+    #[asset(path = "fonts/DejaVuSans.ttf")]
+    pub fallback_font: Handle<Font>,
     #[asset(path = "ui.ess")]
     pub ui_style: Handle<StyleSheet>,
 
@@ -41,6 +44,12 @@ pub struct GameAssets {
     pub shrine_fire: Handle<Image>,
     #[asset(path = "shrine_seed.png")]
     pub shrine_seed: Handle<Image>,
+    #[asset(path = "shrine_swim.png")]
+    pub shrine_swim: Handle<Image>,
+    #[asset(path = "shrine_float.png")]
+    pub shrine_float: Handle<Image>,
+    #[asset(path = "shrine_climb.png")]
+    pub shrine_climb: Handle<Image>,
 
     #[asset(path = "water.png")]
     pub water: Handle<Image>,
@@ -71,6 +80,44 @@ pub struct GameAssets {
 
     #[asset(path = "terra.ogg")]
     pub music: Handle<AudioSource>,
+    #[asset(path = "music/growth.wav")]
+    pub music_growth: Handle<AudioSource>,
+    #[asset(path = "music/tension.wav")]
+    pub music_tension: Handle<AudioSource>,
+
+    #[asset(path = "sfx/fertilize.wav")]
+    pub sfx_fertilize: Handle<AudioSource>,
+    #[asset(path = "sfx/fire.wav")]
+    pub sfx_fire: Handle<AudioSource>,
+    #[asset(path = "sfx/seed.wav")]
+    pub sfx_seed: Handle<AudioSource>,
+    #[asset(path = "sfx/drain.wav")]
+    pub sfx_drain: Handle<AudioSource>,
+    #[asset(path = "sfx/plant.wav")]
+    pub sfx_plant: Handle<AudioSource>,
+    #[asset(path = "sfx/gain_power.wav")]
+    pub sfx_gain_power: Handle<AudioSource>,
+    #[asset(path = "sfx/spread.wav")]
+    pub sfx_spread: Handle<AudioSource>,
+    #[asset(path = "sfx/ignite.wav")]
+    pub sfx_ignite: Handle<AudioSource>,
+    #[asset(path = "sfx/target.wav")]
+    pub sfx_target: Handle<AudioSource>,
+    #[asset(path = "sfx/level_complete.wav")]
+    pub sfx_level_complete: Handle<AudioSource>,
+    #[asset(path = "sfx/hurt.wav")]
+    pub sfx_hurt: Handle<AudioSource>,
+}
+
+impl GameAssets {
+    /// The font for text drawn in the world, which doesn't go through `ui.ess`.
+    pub fn font_for(&self, strings: &Strings) -> Handle<Font> {
+        if strings.uses_fallback_font() {
+            self.fallback_font.clone()
+        } else {
+            self.font.clone()
+        }
+    }
 }
 
 impl FromWorld for PlantDefinitions {
@@ -0,0 +1,266 @@
+use bevy::prelude::*;
+use leafwing_input_manager::{prelude::*, user_input::InputKind};
+use serde::{Deserialize, Serialize};
+
+use crate::{control::Action, storage};
+
+pub struct BindingsPlugin;
+
+impl Plugin for BindingsPlugin {
+    fn build(&self, app: &mut App) {
+        let controls = storage::load::<Controls>(CONTROLS_KEY)
+            .map(Controls::with_missing_defaults)
+            .unwrap_or_default();
+        app.insert_resource(controls).add_system(apply_controls);
+    }
+}
+
+const CONTROLS_KEY: &str = "controls";
+
+/// The inputs bound to every action, persisted between sessions.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Controls(pub Vec<(Action, Vec<UserInput>)>);
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self(vec![
+            (
+                Action::Up,
+                vec![
+                    QwertyScanCode::W.into(),
+                    KeyCode::Up.into(),
+                    GamepadButtonType::DPadUp.into(),
+                ],
+            ),
+            (
+                Action::Down,
+                vec![
+                    QwertyScanCode::S.into(),
+                    KeyCode::Down.into(),
+                    GamepadButtonType::DPadDown.into(),
+                ],
+            ),
+            (
+                Action::Left,
+                vec![
+                    QwertyScanCode::A.into(),
+                    KeyCode::Left.into(),
+                    GamepadButtonType::DPadLeft.into(),
+                ],
+            ),
+            (
+                Action::Right,
+                vec![
+                    QwertyScanCode::D.into(),
+                    KeyCode::Right.into(),
+                    GamepadButtonType::DPadRight.into(),
+                ],
+            ),
+            (
+                Action::Fertilize,
+                vec![KeyCode::Z.into(), GamepadButtonType::South.into()],
+            ),
+            (
+                Action::Fire,
+                vec![KeyCode::X.into(), GamepadButtonType::East.into()],
+            ),
+            (
+                Action::Seed,
+                vec![KeyCode::C.into(), GamepadButtonType::West.into()],
+            ),
+            (
+                Action::Drain,
+                vec![KeyCode::V.into(), GamepadButtonType::North.into()],
+            ),
+            (
+                Action::Plant,
+                vec![KeyCode::B.into(), GamepadButtonType::RightTrigger.into()],
+            ),
+            (
+                Action::Pause,
+                vec![KeyCode::Space.into(), GamepadButtonType::Start.into()],
+            ),
+            (Action::Step, vec![KeyCode::Period.into()]),
+            (Action::Faster, vec![KeyCode::Equals.into()]),
+            (Action::Slower, vec![KeyCode::Minus.into()]),
+            (Action::Predict, vec![KeyCode::G.into()]),
+            (Action::InspectTile, vec![KeyCode::T.into()]),
+            (Action::ZoomIn, vec![KeyCode::PageUp.into()]),
+            (Action::ZoomOut, vec![KeyCode::PageDown.into()]),
+            (Action::FitView, vec![KeyCode::Home.into()]),
+            (Action::FollowPlayer, vec![KeyCode::F.into()]),
+            (Action::Minimap, vec![KeyCode::M.into()]),
+            (Action::PanUp, vec![KeyCode::I.into()]),
+            (Action::PanDown, vec![KeyCode::K.into()]),
+            (Action::PanLeft, vec![KeyCode::J.into()]),
+            (Action::PanRight, vec![KeyCode::L.into()]),
+            (Action::PlayTest, vec![KeyCode::Tab.into()]),
+        ])
+    }
+}
+
+impl Controls {
+    /// Binds any actions added since the controls were saved to their default inputs.
+    fn with_missing_defaults(mut self) -> Self {
+        for (action, inputs) in Controls::default().0 {
+            if !self.0.iter().any(|(a, _)| *a == action) {
+                self.0.push((action, inputs));
+            }
+        }
+        self
+    }
+
+    pub fn input_map(&self) -> InputMap<Action> {
+        InputMap::new(
+            self.0
+                .iter()
+                .flat_map(|(action, inputs)| inputs.iter().map(|input| (input.clone(), *action))),
+        )
+    }
+
+    /// Just the actions that play the level, for the player. The rest are shortcuts read
+    /// from the global `ActionState`, and shouldn't look like the player acting.
+    pub fn player_input_map(&self) -> InputMap<Action> {
+        InputMap::new(
+            self.0
+                .iter()
+                .filter(|(action, _)| action.plays())
+                .flat_map(|(action, inputs)| inputs.iter().map(|input| (input.clone(), *action))),
+        )
+    }
+
+    pub fn inputs(&self, action: Action) -> Vec<UserInput> {
+        self.0
+            .iter()
+            .filter(|(a, _)| *a == action)
+            .flat_map(|(_, inputs)| inputs.iter().cloned())
+            .collect()
+    }
+
+    /// The label of the first keyboard binding for an action, for display on the cards.
+    pub fn label(&self, action: Action) -> String {
+        self.inputs(action)
+            .iter()
+            .find(|input| !is_gamepad(input))
+            .or_else(|| self.inputs(action).first())
+            .map(input_label)
+            .unwrap_or_default()
+    }
+
+    /// Binds an input to an action in place of its first input of the same kind, keyboard
+    /// or gamepad, keeping any alternates. The input is taken off any other action it was
+    /// bound to, so one press never does two things, and those actions are returned.
+    pub fn rebind(&mut self, action: Action, input: UserInput) -> Vec<Action> {
+        let mut taken_from = vec![];
+        for (a, inputs) in self.0.iter_mut() {
+            if *a != action && inputs.contains(&input) {
+                inputs.retain(|i| *i != input);
+                taken_from.push(*a);
+            }
+        }
+
+        if !self.0.iter().any(|(a, _)| *a == action) {
+            self.0.push((action, vec![]));
+        }
+        let gamepad = is_gamepad(&input);
+        for (a, inputs) in self.0.iter_mut() {
+            if *a != action || inputs.contains(&input) {
+                continue;
+            }
+            match inputs.iter().position(|i| is_gamepad(i) == gamepad) {
+                Some(index) => inputs[index] = input.clone(),
+                None => inputs.push(input.clone()),
+            }
+        }
+        taken_from
+    }
+
+    pub fn save(&self) {
+        storage::save(CONTROLS_KEY, self);
+    }
+}
+
+/// Keys the game keeps for itself - Escape backs out, and F12 opens the debug inspector.
+const RESERVED_KEYS: [KeyCode; 2] = [KeyCode::Escape, KeyCode::F12];
+
+pub fn is_reserved(input: &UserInput) -> bool {
+    matches!(input, UserInput::Single(InputKind::Keyboard(key)) if RESERVED_KEYS.contains(key))
+}
+
+pub fn is_gamepad(input: &UserInput) -> bool {
+    matches!(input, UserInput::Single(InputKind::GamepadButton(_)))
+}
+
+pub fn input_label(input: &UserInput) -> String {
+    match input {
+        UserInput::Single(InputKind::Keyboard(key)) => format!("{key:?}"),
+        UserInput::Single(InputKind::GamepadButton(button)) => format!("{button:?}"),
+        input => input.to_string(),
+    }
+}
+
+/// Run condition for the shortcuts that aren't tied to the player, like `input_just_pressed`.
+pub fn action_just_pressed(action: Action) -> impl FnMut(Res<ActionState<Action>>) -> bool + Clone {
+    move |actions: Res<ActionState<Action>>| actions.just_pressed(action)
+}
+
+fn apply_controls(
+    controls: Res<Controls>,
+    mut shortcuts: ResMut<InputMap<Action>>,
+    mut players: Query<&mut InputMap<Action>>,
+) {
+    if !controls.is_changed() {
+        return;
+    }
+    *shortcuts = controls.input_map();
+    for mut input_map in players.iter_mut() {
+        *input_map = controls.player_input_map();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_takes_the_input_from_other_actions() {
+        let mut controls = Controls::default();
+        let taken = controls.rebind(Action::Up, KeyCode::Z.into());
+        assert_eq!(taken, vec![Action::Fertilize]);
+        assert!(controls.inputs(Action::Up).contains(&KeyCode::Z.into()));
+        assert!(!controls
+            .inputs(Action::Fertilize)
+            .contains(&KeyCode::Z.into()));
+    }
+
+    #[test]
+    fn rebinding_keeps_the_alternates() {
+        let mut controls = Controls::default();
+        controls.rebind(Action::Up, KeyCode::Q.into());
+        assert_eq!(
+            controls.inputs(Action::Up),
+            vec![
+                KeyCode::Q.into(),
+                KeyCode::Up.into(),
+                GamepadButtonType::DPadUp.into()
+            ]
+        );
+    }
+
+    #[test]
+    fn rebinding_a_gamepad_button_leaves_the_keys() {
+        let mut controls = Controls::default();
+        controls.rebind(Action::Fire, GamepadButtonType::LeftTrigger.into());
+        assert_eq!(
+            controls.inputs(Action::Fire),
+            vec![KeyCode::X.into(), GamepadButtonType::LeftTrigger.into()]
+        );
+    }
+
+    #[test]
+    fn escape_and_f12_are_reserved() {
+        assert!(is_reserved(&KeyCode::Escape.into()));
+        assert!(is_reserved(&KeyCode::F12.into()));
+        assert!(!is_reserved(&KeyCode::Z.into()));
+    }
+}
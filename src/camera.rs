@@ -0,0 +1,361 @@
+//! Zooming and panning around the level, fitting the whole level on screen when it loads,
+//! and a minimap in the corner showing the ground and plants.
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_vector_shapes::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    colors::Palette,
+    control::{Action, Player},
+    editor::EditorLevel,
+    generate_tiles::LevelLoaded,
+    level_asset::{CurrentLevel, LevelAsset},
+    settings::Settings,
+    states::AppState,
+    tile::{Ground, Plant, PlantDefinitions, Tile, TILE_WORLD_SIZE},
+};
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraView>()
+            .add_event::<CameraControl>()
+            .add_system(
+                fit_to_level.run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            )
+            .add_system(reset_view.in_schedule(OnEnter(AppState::Editor)))
+            .add_system(focus_editor.in_set(OnUpdate(AppState::Editor)))
+            .add_system(camera_input.in_set(OnUpdate(AppState::InGame)))
+            .add_system(camera_input.in_set(OnUpdate(AppState::Editor)))
+            .add_system(control_camera.after(camera_input).after(fit_to_level))
+            .add_system(move_camera.after(control_camera))
+            .add_system(
+                draw_minimap
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(move_camera),
+            );
+    }
+}
+
+/// How far the camera sits above the tiles. Anything drawn higher than this is hidden.
+const CAMERA_HEIGHT: f32 = 200.;
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 4.;
+/// How much a single zoom step, or a notch of the mouse wheel, changes the zoom.
+const ZOOM_STEP: f32 = 1.25;
+/// Room left around the level when fitting it on screen.
+const FIT_MARGIN: f32 = 1.2;
+/// How fast the keys pan the camera, in screen pixels per second.
+const PAN_SPEED: f32 = 500.;
+const EASE_SPEED: f32 = 10.;
+
+const MINIMAP_MARGIN: f32 = 10.;
+const MINIMAP_MAX_SIZE: f32 = 200.;
+const MINIMAP_MAX_CELL: f32 = 10.;
+
+/// Where the camera is looking and how far it is zoomed out.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct CameraView {
+    /// The orthographic scale - above 1 shows more of the level.
+    pub zoom: f32,
+    /// An offset from whatever the camera is centred on, in world units.
+    pub pan: Vec2,
+    /// Centres on the whole level rather than following the player.
+    pub fit: bool,
+    pub minimap: bool,
+    /// The level's extent, in tiles.
+    pub bounds: Option<Rect>,
+    /// Jumps straight to the target on the next frame instead of easing there.
+    snap: bool,
+}
+
+impl Default for CameraView {
+    fn default() -> Self {
+        Self {
+            zoom: 1.,
+            pan: Vec2::ZERO,
+            fit: false,
+            minimap: true,
+            bounds: None,
+            snap: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraControl {
+    ZoomIn,
+    ZoomOut,
+    /// Fits the whole level on screen.
+    Fit,
+    /// Goes back to following the player.
+    Follow,
+    ToggleMinimap,
+}
+
+fn level_bounds(level: &LevelAsset) -> Option<Rect> {
+    let mut tiles = level.tiles.0.keys();
+    let first = tiles.next()?;
+    let start = Vec2::new(first.0 as f32, first.1 as f32);
+    Some(
+        tiles.fold(Rect::from_corners(start, start), |bounds, tile| {
+            bounds.union_point(Vec2::new(tile.0 as f32, tile.1 as f32))
+        }),
+    )
+}
+
+/// The zoom that fits the bounds, with a margin, inside the window.
+fn fit_zoom(bounds: Rect, window: &Window) -> f32 {
+    let size = (bounds.size() + Vec2::ONE) * TILE_WORLD_SIZE * FIT_MARGIN;
+    let zoom = (size.x / window.width()).max(size.y / window.height());
+    // Small levels stay at their natural size rather than being blown up.
+    zoom.clamp(1., MAX_ZOOM)
+}
+
+fn fit_to_level(
+    mut view: ResMut<CameraView>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<LevelAsset>>,
+    mut controls: EventWriter<CameraControl>,
+) {
+    let level = current_level
+        .0
+        .as_ref()
+        .and_then(|handle| levels.get(handle));
+    view.bounds = level.and_then(level_bounds);
+    view.snap = true;
+    controls.send(CameraControl::Fit);
+}
+
+fn reset_view(mut view: ResMut<CameraView>) {
+    *view = CameraView::default();
+}
+
+/// The editor has no player to follow, so the camera looks at the grid being edited.
+fn focus_editor(mut view: ResMut<CameraView>, level: Res<EditorLevel>) {
+    let bounds = Some(level.bounds());
+    if view.bounds != bounds {
+        view.bounds = bounds;
+    }
+}
+
+fn camera_input(
+    actions: Res<ActionState<Action>>,
+    mouse: Res<Input<MouseButton>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut motion: EventReader<MouseMotion>,
+    mut view: ResMut<CameraView>,
+    mut controls: EventWriter<CameraControl>,
+    time: Res<Time>,
+) {
+    if actions.just_pressed(Action::ZoomIn) {
+        controls.send(CameraControl::ZoomIn);
+    }
+    if actions.just_pressed(Action::ZoomOut) {
+        controls.send(CameraControl::ZoomOut);
+    }
+    if actions.just_pressed(Action::FitView) {
+        controls.send(CameraControl::Fit);
+    }
+    if actions.just_pressed(Action::FollowPlayer) {
+        controls.send(CameraControl::Follow);
+    }
+    if actions.just_pressed(Action::Minimap) {
+        controls.send(CameraControl::ToggleMinimap);
+    }
+
+    let notches = wheel
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.,
+        })
+        .sum::<f32>();
+    if notches != 0. {
+        view.zoom = (view.zoom * ZOOM_STEP.powf(-notches)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    // Dragging with the right or middle button moves the level along with the cursor.
+    let dragged = motion.iter().map(|event| event.delta).sum::<Vec2>();
+    let mut pan = if mouse.pressed(MouseButton::Right) || mouse.pressed(MouseButton::Middle) {
+        Vec2::new(-dragged.x, dragged.y)
+    } else {
+        Vec2::ZERO
+    };
+
+    let direction = [
+        (Action::PanUp, Vec2::Y),
+        (Action::PanDown, Vec2::NEG_Y),
+        (Action::PanLeft, Vec2::NEG_X),
+        (Action::PanRight, Vec2::X),
+    ]
+    .iter()
+    .filter(|(action, _)| actions.pressed(*action))
+    .map(|(_, direction)| *direction)
+    .sum::<Vec2>();
+    pan += direction * PAN_SPEED * time.delta_seconds();
+
+    if pan != Vec2::ZERO {
+        let zoom = view.zoom;
+        view.pan += pan * zoom;
+    }
+}
+
+fn control_camera(
+    mut controls: EventReader<CameraControl>,
+    mut view: ResMut<CameraView>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    for control in controls.iter() {
+        match control {
+            CameraControl::ZoomIn => view.zoom = (view.zoom / ZOOM_STEP).max(MIN_ZOOM),
+            CameraControl::ZoomOut => view.zoom = (view.zoom * ZOOM_STEP).min(MAX_ZOOM),
+            CameraControl::Fit => {
+                let Ok(window) = windows.get_single() else { continue; };
+                let Some(bounds) = view.bounds else { continue; };
+                view.zoom = fit_zoom(bounds, window);
+                view.pan = Vec2::ZERO;
+                view.fit = true;
+            }
+            CameraControl::Follow => {
+                view.pan = Vec2::ZERO;
+                view.fit = false;
+            }
+            CameraControl::ToggleMinimap => view.minimap = !view.minimap,
+        }
+    }
+}
+
+fn move_camera(
+    mut view: ResMut<CameraView>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+    players: Query<&Transform, (With<Player>, Without<Camera2d>)>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let level = view.bounds.map(|bounds| bounds.center() * TILE_WORLD_SIZE);
+    let player = players.iter().next().map(|t| t.translation.truncate());
+    // Without a player to follow, the camera stays on the level.
+    let focus = if view.fit {
+        level.or(player)
+    } else {
+        player.or(level)
+    };
+    let Some(focus) = focus else { return; };
+
+    let ease = if settings.reduced_motion || view.snap {
+        1.
+    } else {
+        1. - (-EASE_SPEED * time.delta_seconds()).exp()
+    };
+    let target = (focus + view.pan).extend(CAMERA_HEIGHT);
+
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        transform.translation = transform.translation.lerp(target, ease);
+        projection.scale += (view.zoom - projection.scale) * ease;
+    }
+
+    if view.snap {
+        view.bypass_change_detection().snap = false;
+    }
+}
+
+fn ground_color(ground: &Ground) -> Color {
+    match ground {
+        Ground::Soil(false) => Color::rgb(0.45, 0.33, 0.22),
+        Ground::Soil(true) => Color::rgb(0.35, 0.25, 0.15),
+        Ground::Sand(false) => Color::rgb(0.85, 0.78, 0.55),
+        Ground::Sand(true) => Color::rgb(0.75, 0.66, 0.42),
+        Ground::Rock(false) => Color::rgb(0.55, 0.55, 0.58),
+        Ground::Rock(true) => Color::rgb(0.42, 0.42, 0.46),
+        Ground::Water => Color::rgb(0.25, 0.45, 0.8),
+        Ground::Empty => Color::NONE,
+    }
+}
+
+/// Where the minimap sits in the window, and how big each of its tiles is.
+fn minimap_layout(bounds: Rect, window: &Window) -> (Rect, f32) {
+    let tiles_across = bounds.size() + Vec2::ONE;
+    let cell = (MINIMAP_MAX_SIZE / tiles_across.max_element()).min(MINIMAP_MAX_CELL);
+    let size = tiles_across * cell;
+    let screen = Vec2::new(window.width(), window.height());
+    let center = screen - size / 2. - MINIMAP_MARGIN;
+    let area = Rect::from_center_size(center, size + Vec2::splat(cell));
+    (area, cell)
+}
+
+/// The part of the window covered by the minimap, if it's showing.
+pub fn minimap_area(view: &CameraView, window: &Window) -> Option<Rect> {
+    let bounds = view.bounds.filter(|_| view.minimap)?;
+    Some(minimap_layout(bounds, window).0)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_minimap(
+    mut painter: ShapePainter,
+    view: Res<CameraView>,
+    cameras: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    tiles: Query<(&Tile, &Ground, &Plant)>,
+    players: Query<&Player>,
+    plants: Res<PlantDefinitions>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+) {
+    if !view.minimap {
+        return;
+    }
+    let Some(bounds) = view.bounds else { return; };
+    let Ok((camera, projection)) = cameras.get_single() else { return; };
+    let Ok(window) = windows.get_single() else { return; };
+
+    // The minimap is drawn in the world, so everything is scaled to stay the same size on screen.
+    let scale = projection.scale;
+    let (area, cell) = minimap_layout(bounds, window);
+    let screen = Vec2::new(window.width(), window.height());
+    let center = camera.translation.truncate() + (area.center() - screen / 2.) * scale;
+    let z = CAMERA_HEIGHT - 10.;
+
+    let cell_position = |x: f32, y: f32| {
+        let offset = (Vec2::new(x, y) - bounds.center()) * cell;
+        (center + offset * scale).extend(z + 1.)
+    };
+
+    painter.hollow = false;
+    painter.set_translation(center.extend(z));
+    painter.color = palette.background.with_a(0.85);
+    painter.rect(area.size() * scale);
+
+    for (tile, ground, plant) in tiles.iter() {
+        let color = match plant {
+            Plant::Empty => ground_color(ground),
+            Plant::Plant(id) => plants.color(id, settings.plant_palette),
+            Plant::Fire(_) => palette.danger,
+        };
+        painter.set_translation(cell_position(tile.0 as f32, tile.1 as f32));
+        painter.color = color;
+        painter.rect(Vec2::splat(cell * scale));
+    }
+
+    for player in players.iter() {
+        painter.set_translation(cell_position(player.0 as f32, player.1 as f32) + Vec3::Z);
+        painter.color = palette.pale;
+        painter.circle(cell * scale * 0.4);
+    }
+
+    // Outline the part of the level currently on screen.
+    let visible = screen * scale / TILE_WORLD_SIZE;
+    let looking_at = camera.translation.truncate() / TILE_WORLD_SIZE;
+    painter.hollow = true;
+    painter.thickness = scale;
+    painter.set_translation(cell_position(looking_at.x, looking_at.y) + Vec3::Z);
+    painter.color = palette.secondary;
+    painter.rect(visible * cell * scale);
+}
@@ -0,0 +1,177 @@
+//! Controls how fast the simulation runs - pausing it, stepping a single generation,
+//! or speeding it up and slowing it down.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    control::Action,
+    generate_tiles::LevelLoaded,
+    history::PendingRestore,
+    level_asset::{CurrentLevel, LevelAsset},
+    locale::Strings,
+    settings::Settings,
+    states::AppState,
+};
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulationClock>()
+            .add_event::<ClockControl>()
+            .add_system(reset_clock.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(
+                reset_clock.run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            )
+            .add_system(clock_keys.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                control_clock
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(clock_keys),
+            )
+            .add_system(
+                tick_clock
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(control_clock),
+            );
+    }
+}
+
+/// How long a generation takes at normal speed.
+pub const GENERATION_SECONDS: f32 = 0.5;
+
+/// The speeds the clock can run at, slowest first.
+pub const SPEEDS: [f32; 4] = [0.5, 1., 2., 4.];
+
+pub const DEFAULT_SPEED: f32 = 1.;
+
+#[derive(Resource, Debug, Clone)]
+pub struct SimulationClock {
+    pub paused: bool,
+    pub speed: f32,
+    timer: Timer,
+    /// Set once a generation is due, and cleared when it runs.
+    ready: bool,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self::new(DEFAULT_SPEED)
+    }
+}
+
+impl SimulationClock {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            paused: false,
+            speed,
+            timer: Timer::new(
+                Duration::from_secs_f32(GENERATION_SECONDS),
+                TimerMode::Repeating,
+            ),
+            ready: false,
+        }
+    }
+
+    pub fn label(&self, strings: &Strings) -> String {
+        if self.paused {
+            strings.get("clock.paused")
+        } else {
+            format!("{}x", self.speed)
+        }
+    }
+
+    /// Marks the generation as having run.
+    pub fn consume(&mut self) {
+        self.ready = false;
+    }
+
+    fn change_speed(&mut self, steps: isize) {
+        let current = SPEEDS
+            .iter()
+            .position(|s| *s >= self.speed)
+            .unwrap_or(SPEEDS.len() - 1) as isize;
+        let next = (current + steps).clamp(0, SPEEDS.len() as isize - 1);
+        self.speed = SPEEDS[next as usize];
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockControl {
+    TogglePause,
+    /// Runs a single generation, pausing the clock if it wasn't already.
+    Step,
+    Faster,
+    Slower,
+}
+
+/// Run condition for the simulation - true when the clock says a generation is due.
+pub fn generation_due(clock: Res<SimulationClock>) -> bool {
+    clock.ready
+}
+
+fn reset_clock(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<LevelAsset>>,
+    settings: Res<Settings>,
+    pending: Res<PendingRestore>,
+) {
+    // Undoing or resuming picks up with the clock as the player left it, paused or sped up.
+    if pending.0.is_some() {
+        return;
+    }
+    let speed = current_level
+        .0
+        .as_ref()
+        .and_then(|handle| levels.get(handle))
+        .and_then(|level| level.speed)
+        .unwrap_or(settings.simulation_speed);
+    commands.insert_resource(SimulationClock::new(speed));
+}
+
+fn clock_keys(actions: Res<ActionState<Action>>, mut controls: EventWriter<ClockControl>) {
+    if actions.just_pressed(Action::Pause) {
+        controls.send(ClockControl::TogglePause);
+    }
+    if actions.just_pressed(Action::Step) {
+        controls.send(ClockControl::Step);
+    }
+    if actions.just_pressed(Action::Faster) {
+        controls.send(ClockControl::Faster);
+    }
+    if actions.just_pressed(Action::Slower) {
+        controls.send(ClockControl::Slower);
+    }
+}
+
+fn control_clock(mut controls: EventReader<ClockControl>, mut clock: ResMut<SimulationClock>) {
+    for control in controls.iter() {
+        match control {
+            ClockControl::TogglePause => clock.paused = !clock.paused,
+            ClockControl::Step => {
+                clock.paused = true;
+                clock.ready = true;
+            }
+            ClockControl::Faster => clock.change_speed(1),
+            ClockControl::Slower => clock.change_speed(-1),
+        }
+    }
+}
+
+pub fn tick_clock(mut clock: ResMut<SimulationClock>, time: Res<Time>) {
+    if clock.paused {
+        return;
+    }
+    // Ticking happens every frame, so it doesn't count as a change for anything showing the clock.
+    let clock = clock.bypass_change_detection();
+    let delta = time.delta().mul_f32(clock.speed);
+    // A slow frame at high speed can finish the timer more than once - that still only runs
+    // one generation, but it mustn't be missed.
+    if clock.timer.tick(delta).times_finished_this_tick() > 0 {
+        clock.ready = true;
+    }
+}
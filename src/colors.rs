@@ -1,8 +1,79 @@
 #![allow(dead_code)]
-use bevy::prelude::Color;
+use bevy::prelude::{Color, Resource};
+use serde::{Deserialize, Serialize};
 
 pub const BACKGROUND: Color = Color::rgb(0.27, 0.3, 0.4);
 pub const DARK: Color = Color::rgb(0.19, 0.6, 0.46);
 pub const LIGHT: Color = Color::rgb(0.35, 0.7, 0.4);
 pub const SECONDARY: Color = Color::rgb(0.85, 0.85, 0.45);
 pub const PALE: Color = Color::rgb(0.94, 0.93, 0.71);
+pub const DANGER: Color = Color::rgb(0.9, 0.35, 0.2);
+
+/// The colours used for anything drawn in the world rather than styled in `ui.ess`,
+/// swapped for a stronger set when high contrast is turned on in the settings.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub background: Color,
+    pub dark: Color,
+    pub light: Color,
+    pub secondary: Color,
+    pub pale: Color,
+    pub danger: Color,
+}
+
+impl Palette {
+    pub const STANDARD: Palette = Palette {
+        background: BACKGROUND,
+        dark: DARK,
+        light: LIGHT,
+        secondary: SECONDARY,
+        pale: PALE,
+        danger: DANGER,
+    };
+
+    pub const HIGH_CONTRAST: Palette = Palette {
+        background: Color::rgb(0.05, 0.05, 0.08),
+        dark: Color::rgb(0., 0.45, 0.3),
+        light: Color::rgb(0.3, 1., 0.45),
+        secondary: Color::rgb(1., 0.9, 0.),
+        pale: Color::rgb(1., 1., 1.),
+        danger: Color::rgb(1., 0.2, 0.1),
+    };
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// The Okabe-Ito palette, which stays distinguishable with the common kinds of colour blindness.
+pub const OKABE_ITO: [Color; 7] = [
+    Color::rgb(0.9, 0.62, 0.),
+    Color::rgb(0.34, 0.71, 0.91),
+    Color::rgb(0., 0.62, 0.45),
+    Color::rgb(0.94, 0.89, 0.26),
+    Color::rgb(0., 0.45, 0.7),
+    Color::rgb(0.84, 0.37, 0.),
+    Color::rgb(0.8, 0.47, 0.65),
+];
+
+/// How plants are tinted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlantPalette {
+    /// The colours the plant definitions ask for.
+    #[default]
+    Standard,
+    /// Colours picked to stay distinct with colour blindness.
+    ColourBlind,
+}
+
+impl PlantPalette {
+    /// The key of the palette's name in the string tables.
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            PlantPalette::Standard => "palette.standard",
+            PlantPalette::ColourBlind => "palette.colour_blind",
+        }
+    }
+}
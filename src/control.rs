@@ -1,9 +1,14 @@
 use bevy::{prelude::*, utils::HashMap};
 use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     assets::GameAssets,
+    bindings::Controls,
     generate_tiles::LevelLoaded,
+    hazards::PlayerRules,
+    movement::Abilities,
+    settings::Settings,
     states::AppState,
     tile::{Ground, Plant, Tile, TILE_WORLD_SIZE},
 };
@@ -14,9 +19,13 @@ impl Plugin for ControlPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<AvailablePowers>()
             .init_resource::<Seed>()
+            .init_resource::<PowerTarget>()
             .add_event::<GainPower>()
             .add_event::<UsePower>()
             .add_plugin(InputManagerPlugin::<Action>::default())
+            // Shortcuts for the clock, camera and overlays work in every state, player or not.
+            .init_resource::<ActionState<Action>>()
+            .init_resource::<InputMap<Action>>()
             .add_system(setup_player.in_set(OnUpdate(AppState::InGame)))
             .add_system(
                 move_player
@@ -34,6 +43,9 @@ impl Plugin for ControlPlugin {
     }
 }
 
+/// How quickly the player sprite catches up with the player's tile.
+const FOLLOW_SPEED: f32 = 15.;
+
 #[derive(Component)]
 pub struct Player(pub i8, pub i8);
 
@@ -43,7 +55,7 @@ pub struct GainPower(pub Power);
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct UsePower(pub Power, pub Tile);
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Power {
     Fertilize,
     Fire,
@@ -52,8 +64,10 @@ pub enum Power {
     Plant,
 }
 
-#[derive(Resource, Default, Debug, Clone, PartialEq)]
-pub struct Seed(pub Option<(String, String, Color)>);
+/// The plant id and image of the seed held for the plant card. Its colour isn't kept,
+/// so it's always looked up with the current plant palette.
+#[derive(Resource, Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Seed(pub Option<(String, String)>);
 
 impl ToString for Power {
     fn to_string(&self) -> String {
@@ -69,36 +83,58 @@ impl ToString for Power {
 }
 
 impl Power {
+    /// The key of the power's name in the string tables. `to_string` stays in English for style classes.
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Power::Fertilize => "power.fertilize",
+            Power::Fire => "power.fire",
+            Power::Seed => "power.seed",
+            Power::Drain => "power.drain",
+            Power::Plant => "power.plant",
+        }
+    }
+
     pub fn ui_class_name(&self) -> String {
         let name = self.to_string();
         format!("card {name}")
     }
 
-    pub fn ui_image(&self, seed: &Seed) -> (String, Option<Color>) {
+    pub fn ui_image(&self, seed: &Seed) -> String {
         match self {
-            Power::Fertilize => ("card_fertilize.png".to_string(), None),
-            Power::Fire => ("card_fire.png".to_string(), None),
-            Power::Seed => ("card_seed.png".to_string(), None),
-            Power::Drain => ("card_drain.png".to_string(), None),
+            Power::Fertilize => "card_fertilize.png".to_string(),
+            Power::Fire => "card_fire.png".to_string(),
+            Power::Seed => "card_seed.png".to_string(),
+            Power::Drain => "card_drain.png".to_string(),
             Power::Plant => match &seed.0 {
-                Some((_, url, color)) => (url.to_string(), Some(*color)),
-                None => ("goal_pillar.png".to_string(), None),
+                Some((_, url)) => url.to_string(),
+                None => "goal_pillar.png".to_string(),
             },
         }
     }
 
-    pub fn key_binding(&self) -> String {
+    pub fn action(&self) -> Action {
         match self {
-            Power::Fertilize => "Z",
-            Power::Fire => "X",
-            Power::Seed => "C",
-            Power::Drain => "V",
-            Power::Plant => "B",
+            Power::Fertilize => Action::Fertilize,
+            Power::Fire => Action::Fire,
+            Power::Seed => Action::Seed,
+            Power::Drain => Action::Drain,
+            Power::Plant => Action::Plant,
+        }
+    }
+
+    /// How many tiles away from the player this power can be aimed with the pointer.
+    pub fn range(&self) -> u8 {
+        match self {
+            Power::Fire => 2,
+            _ => 1,
         }
-        .to_string()
     }
 }
 
+/// Overrides the tile the next power is used on, instead of the player's own tile.
+#[derive(Resource, Default, Debug, Clone, PartialEq, Eq)]
+pub struct PowerTarget(pub Option<Tile>);
+
 #[derive(Resource, Clone, Default)]
 pub struct AvailablePowers(pub HashMap<Power, usize>);
 
@@ -134,12 +170,14 @@ impl AvailablePowers {
 fn reset_available_powers(mut commands: Commands) {
     commands.insert_resource(AvailablePowers::default());
     commands.insert_resource(Seed::default());
+    commands.insert_resource(PowerTarget::default());
 }
 
 fn setup_player(
     mut commands: Commands,
     players: Query<(Entity, &Player), Without<Sprite>>,
     assets: Res<GameAssets>,
+    controls: Res<Controls>,
 ) {
     for (e, player) in players.iter() {
         commands.entity(e).insert((
@@ -158,45 +196,37 @@ fn setup_player(
                 // Stores "which actions are currently pressed"
                 action_state: ActionState::default(),
                 // Describes how to convert from player inputs into those actions
-                input_map: InputMap::new([
-                    (UserInput::from(QwertyScanCode::W), Action::Up),
-                    (QwertyScanCode::S.into(), Action::Down),
-                    (QwertyScanCode::A.into(), Action::Left),
-                    (QwertyScanCode::D.into(), Action::Right),
-                    (KeyCode::Up.into(), Action::Up),
-                    (KeyCode::Down.into(), Action::Down),
-                    (KeyCode::Left.into(), Action::Left),
-                    (KeyCode::Right.into(), Action::Right),
-                    (KeyCode::Z.into(), Action::Fertilize),
-                    (KeyCode::C.into(), Action::Seed),
-                    (KeyCode::V.into(), Action::Drain),
-                    (KeyCode::X.into(), Action::Fire),
-                    (KeyCode::B.into(), Action::Plant),
-                ]),
+                input_map: controls.player_input_map(),
             },
         ));
     }
 }
 
 fn set_player_position(
-    mut players: Query<(&Player, &mut Transform), Changed<Player>>,
-    mut camera: Query<&mut Transform, (With<Camera2d>, Without<Player>)>,
+    mut players: Query<(Ref<Player>, &mut Transform)>,
+    settings: Res<Settings>,
+    time: Res<Time>,
 ) {
-    let mut position = None;
-    for (p, mut t) in players.iter_mut() {
-        t.translation = Vec3::new(p.0 as f32, p.1 as f32, 2.) * TILE_WORLD_SIZE;
-        position = Some(t.translation);
-    }
+    // The sprite eases towards the player's tile, rather than snapping to it.
+    let follow = if settings.reduced_motion {
+        1.
+    } else {
+        1. - (-FOLLOW_SPEED * time.delta_seconds()).exp()
+    };
 
-    let Some(position) = position else {return;};
-
-    for mut c in camera.iter_mut() {
-        c.translation = position + Vec3::Z * 30.;
+    for (p, mut t) in players.iter_mut() {
+        let target = Vec3::new(p.0 as f32, p.1 as f32, 2.) * TILE_WORLD_SIZE;
+        // A player that was only just placed, like after a restart, starts on its tile.
+        t.translation = if p.is_added() {
+            target
+        } else {
+            t.translation.lerp(target, follow)
+        };
     }
 }
 
-#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
-enum Action {
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
     Up,
     Down,
     Left,
@@ -206,54 +236,130 @@ enum Action {
     Seed,
     Drain,
     Plant,
+    Pause,
+    Step,
+    Faster,
+    Slower,
+    Predict,
+    InspectTile,
+    ZoomIn,
+    ZoomOut,
+    FitView,
+    FollowPlayer,
+    Minimap,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    PlayTest,
+}
+
+impl Action {
+    /// Whether the action moves the player or uses a power, rather than working the clock,
+    /// camera or overlays. Only these are recorded in replays.
+    pub fn plays(&self) -> bool {
+        matches!(
+            self,
+            Action::Up
+                | Action::Down
+                | Action::Left
+                | Action::Right
+                | Action::Fertilize
+                | Action::Fire
+                | Action::Seed
+                | Action::Drain
+                | Action::Plant
+        )
+    }
+
+    /// The key of the action's name in the string tables.
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Action::Up => "action.up",
+            Action::Down => "action.down",
+            Action::Left => "action.left",
+            Action::Right => "action.right",
+            Action::Fertilize => "action.fertilize",
+            Action::Fire => "action.fire",
+            Action::Seed => "action.seed",
+            Action::Drain => "action.drain",
+            Action::Plant => "action.plant",
+            Action::Pause => "action.pause",
+            Action::Step => "action.step",
+            Action::Faster => "action.faster",
+            Action::Slower => "action.slower",
+            Action::Predict => "action.predict",
+            Action::InspectTile => "action.inspect_tile",
+            Action::ZoomIn => "action.zoom_in",
+            Action::ZoomOut => "action.zoom_out",
+            Action::FitView => "action.fit_view",
+            Action::FollowPlayer => "action.follow_player",
+            Action::Minimap => "action.minimap",
+            Action::PanUp => "action.pan_up",
+            Action::PanDown => "action.pan_down",
+            Action::PanLeft => "action.pan_left",
+            Action::PanRight => "action.pan_right",
+            Action::PlayTest => "action.play_test",
+        }
+    }
 }
 
-fn move_player(
+#[allow(clippy::too_many_arguments)]
+pub fn move_player(
     mut player: Query<(&mut Player, &ActionState<Action>)>,
     query: Query<(&Tile, &Ground, &Plant)>,
     mut use_power: EventWriter<UsePower>,
     powers: Res<AvailablePowers>,
+    abilities: Res<Abilities>,
+    rules: Res<PlayerRules>,
+    mut power_target: ResMut<PowerTarget>,
 ) {
+    let tiles = query
+        .iter()
+        .map(|(t, g, p)| (*t, (g, p)))
+        .collect::<HashMap<_, _>>();
+
     for (mut p, a) in player.iter_mut() {
-        let mut target = Tile(p.0, p.1);
+        let mut direction = (0, 0);
         if a.just_pressed(Action::Up) {
-            target.1 += 1;
+            direction.1 += 1;
         }
         if a.just_pressed(Action::Down) {
-            target.1 -= 1;
+            direction.1 -= 1;
         }
         if a.just_pressed(Action::Left) {
-            target.0 -= 1;
+            direction.0 -= 1;
         }
         if a.just_pressed(Action::Right) {
-            target.0 += 1;
+            direction.0 += 1;
         }
-        if query
-            .iter()
-            .any(|(t, _g, p)| *t == target && !matches!(p, Plant::Empty))
-        {
-            p.0 = target.0;
-            p.1 = target.1;
+        if direction != (0, 0) {
+            if let Some(target) = abilities.step(Tile(p.0, p.1), direction, &tiles, &rules) {
+                p.0 = target.0;
+                p.1 = target.1;
+            }
         }
 
+        let target = power_target.0.take().unwrap_or(Tile(p.0, p.1));
+
         if a.just_pressed(Action::Fertilize) {
-            try_use_power(Power::Fertilize, &powers, &mut use_power, &p);
+            try_use_power(Power::Fertilize, &powers, &mut use_power, target);
         }
 
         if a.just_pressed(Action::Drain) {
-            try_use_power(Power::Drain, &powers, &mut use_power, &p);
+            try_use_power(Power::Drain, &powers, &mut use_power, target);
         }
 
         if a.just_pressed(Action::Seed) {
-            try_use_power(Power::Seed, &powers, &mut use_power, &p);
+            try_use_power(Power::Seed, &powers, &mut use_power, target);
         }
 
         if a.just_pressed(Action::Fire) {
-            try_use_power(Power::Fire, &powers, &mut use_power, &p);
+            try_use_power(Power::Fire, &powers, &mut use_power, target);
         }
 
         if a.just_pressed(Action::Plant) {
-            try_use_power(Power::Plant, &powers, &mut use_power, &p);
+            try_use_power(Power::Plant, &powers, &mut use_power, target);
         }
     }
 }
@@ -262,12 +368,12 @@ fn try_use_power(
     power: Power,
     powers: &Res<AvailablePowers>,
     use_power: &mut EventWriter<UsePower>,
-    p: &Mut<Player>,
+    target: Tile,
 ) {
     let Some(available) = powers.0.get(&power) else {return;};
     let available = *available;
     if available > 0 {
-        use_power.send(UsePower(power, Tile(p.0, p.1)));
+        use_power.send(UsePower(power, target));
     }
 }
 
@@ -0,0 +1,203 @@
+use crate::{
+    assets::GameAssets,
+    bindings::{input_label, is_reserved, Controls},
+    control::Action,
+    locale::Strings,
+    states::AppState,
+};
+use belly::{core::ess::Styles, prelude::*};
+use bevy::prelude::*;
+use leafwing_input_manager::{prelude::UserInput, Actionlike};
+
+pub struct ControlsScreenPlugin;
+
+impl Plugin for ControlsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .init_resource::<RebindMessage>()
+            .add_event::<ControlsEvent>()
+            .add_system(
+                setup_menu
+                    .in_base_set(CoreSet::PreUpdate)
+                    .run_if(in_state(AppState::Controls)),
+            )
+            .add_system(handle_events.in_set(OnUpdate(AppState::Controls)))
+            .add_system(capture_rebind.in_set(OnUpdate(AppState::Controls)))
+            .add_system(clear_menu.in_schedule(OnExit(AppState::Controls)));
+    }
+}
+
+/// The action waiting for its next input, if any.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Rebinding(Option<Action>);
+
+/// What happened to the last input pressed, when it didn't simply bind.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+struct RebindMessage(Option<String>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlsEvent {
+    Rebind(Action),
+    Reset,
+}
+
+#[derive(Component)]
+struct MenuItem;
+
+fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    controls: Res<Controls>,
+    rebinding: Res<Rebinding>,
+    message: Res<RebindMessage>,
+    strings: Res<Strings>,
+    query: Query<Entity, With<MenuItem>>,
+) {
+    if !query.is_empty()
+        && !controls.is_changed()
+        && !rebinding.is_changed()
+        && !message.is_changed()
+        && !strings.is_changed()
+    {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    styles.insert(assets.ui_style.clone());
+    let ui = commands.spawn(MenuItem).id();
+
+    let rows = Action::variants()
+        .map(|action| {
+            let bindings = controls
+                .inputs(action)
+                .iter()
+                .map(input_label)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let button = if rebinding.0 == Some(action) {
+                strings.get("controls.waiting")
+            } else {
+                strings.get("controls.rebind")
+            };
+            (action, strings.get(action.label_key()), bindings, button)
+        })
+        .collect::<Vec<_>>();
+    let title = strings.get("controls.title");
+    let message = message.0.clone().unwrap_or_default();
+    let reset = strings.get("common.reset");
+    let menu = strings.get("common.menu");
+
+    let root = strings.root_class("root");
+    commands.add(eml! {
+        <body {ui} class={root}>
+            <div c:header>{title}</div>
+            <span c:controls_message>{message}</span>
+            <for row in=rows>
+                <div c:controls_row>
+                    <span c:controls_action>{row.1.clone()}</span>
+                    <span c:controls_bindings>{row.2.clone()}</span>
+                    <button c:exit_button on:press=move |ctx| ctx.send_event(ControlsEvent::Rebind(row.0))>
+                        <span c:content>{row.3.clone()}</span>
+                    </button>
+                </div>
+            </for>
+            <button c:menu_button c:small_menu_button c:secondary on:press=|ctx| ctx.send_event(ControlsEvent::Reset)>
+                <span c:content>
+                {reset}
+                </span>
+            </button>
+            <button c:menu_button c:small_menu_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Menu)))>
+                <span c:content>
+                {menu}
+                </span>
+            </button>
+        </body>
+    });
+}
+
+fn handle_events(
+    mut events: EventReader<ControlsEvent>,
+    mut rebinding: ResMut<Rebinding>,
+    mut message: ResMut<RebindMessage>,
+    mut controls: ResMut<Controls>,
+) {
+    for event in events.iter() {
+        message.0 = None;
+        match event {
+            ControlsEvent::Rebind(action) => {
+                rebinding.0 = Some(*action);
+            }
+            ControlsEvent::Reset => {
+                rebinding.0 = None;
+                *controls = Controls::default();
+                controls.save();
+            }
+        }
+    }
+}
+
+fn capture_rebind(
+    mut rebinding: ResMut<Rebinding>,
+    mut message: ResMut<RebindMessage>,
+    mut controls: ResMut<Controls>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    strings: Res<Strings>,
+) {
+    let Some(action) = rebinding.0 else { return; };
+
+    if keys.just_pressed(KeyCode::Escape) {
+        rebinding.0 = None;
+        return;
+    }
+
+    let input = keys
+        .get_just_pressed()
+        .next()
+        .map(|key| UserInput::from(*key))
+        .or_else(|| {
+            buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| UserInput::from(button.button_type))
+        });
+    let Some(input) = input else { return; };
+
+    // Keep waiting for a key that can be used.
+    if is_reserved(&input) {
+        message.0 = Some(strings.format("controls.reserved", &[("key", &input_label(&input))]));
+        return;
+    }
+
+    let taken_from = controls.rebind(action, input.clone());
+    message.0 = (!taken_from.is_empty()).then(|| {
+        let actions = taken_from
+            .iter()
+            .map(|a| strings.get(a.label_key()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        strings.format(
+            "controls.taken",
+            &[("key", &input_label(&input)), ("actions", &actions)],
+        )
+    });
+    controls.save();
+    rebinding.0 = None;
+}
+
+fn clear_menu(
+    mut commands: Commands,
+    query: Query<Entity, With<MenuItem>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut message: ResMut<RebindMessage>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    rebinding.0 = None;
+    message.0 = None;
+}
@@ -1,4 +1,4 @@
-use crate::{assets::GameAssets, states::AppState};
+use crate::{assets::GameAssets, locale::Strings, states::AppState};
 use belly::{core::ess::Styles, prelude::*};
 use bevy::prelude::*;
 
@@ -14,22 +14,39 @@ impl Plugin for CreditsPlugin {
 #[derive(Component)]
 struct MenuItem;
 
-fn setup_menu(mut commands: Commands, assets: Res<GameAssets>, mut styles: ResMut<Styles>) {
+fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    strings: Res<Strings>,
+) {
     styles.insert(assets.ui_style.clone());
     let ui = commands.spawn(MenuItem).id();
 
+    let title = strings.get("menu.title");
+    let design = strings.get("credits.design");
+    let assets_by = strings.get("credits.assets");
+    let font = strings.get("credits.font");
+    let fallback_font = strings.get("credits.fallback_font");
+    let other_assets = strings.get("credits.other_assets");
+    let crates = strings.get("credits.crates");
+    let bevy = strings.get("credits.bevy");
+    let menu = strings.get("common.menu");
+
+    let root = strings.root_class("root");
     commands.add(eml! {
-        <body {ui} c:root>
-            <div c:header>"Terra and Caelus"</div>
+        <body {ui} class={root}>
+            <div c:header>{title}</div>
             <div c:separator></div>
-            <div c:credit>"Design, Development by Lee-Orr"</div>
+            <div c:credit>{design}</div>
             <div c:separator></div>
-            <div c:subheader>"With Assets By"</div>
-            <div c:credit>"Archeologicaps Font by Manfred Klein"</div>
-            <div c:credit>"All Other Art And Music Assets by Lee-Orr"</div>
+            <div c:subheader>{assets_by}</div>
+            <div c:credit>{font}</div>
+            <div c:credit>{fallback_font}</div>
+            <div c:credit>{other_assets}</div>
             <div c:separator></div>
-            <div c:subheader>"Using the following Rust crates:"</div>
-            <div c:credit>"The Bevy Game Engine"</div>
+            <div c:subheader>{crates}</div>
+            <div c:credit>{bevy}</div>
             <div c:credit>"Bevy Asset Loader"</div>
             <div c:credit>"Bevy Common Assets"</div>
             <div c:credit>"Serde"</div>
@@ -43,7 +60,7 @@ fn setup_menu(mut commands: Commands, assets: Res<GameAssets>, mut styles: ResMu
             <div c:credit>"Wasm Server Runner"</div>
             <button c:menu_button c:small_menu_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Menu)))>
                 <span c:content>
-                "Menu"
+                {menu}
                 </span>
             </button>
         </body>
@@ -1,44 +1,119 @@
 use bevy::prelude::*;
 
 use crate::{
+    animation::{Flicker, Tween, TweenKind},
     assets::GameAssets,
+    colors::PlantPalette,
+    settings::Settings,
     states::AppState,
-    tile::{Ground, Plant, PlantDefinitions, TileAsset, FIRE_DURATION, TILE_WORLD_SIZE},
+    tile::{Ground, Plant, PlantDefinitions, FIRE_DURATION, TILE_WORLD_SIZE},
 };
 
 pub struct TileDisplayPlugin;
 
 impl Plugin for TileDisplayPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(display_tiles.in_set(OnUpdate(AppState::InGame)));
+        app.add_system(display_tiles.in_set(OnUpdate(AppState::InGame)))
+            .add_system(display_tiles.in_set(OnUpdate(AppState::Editor)));
     }
 }
 
-type ChangedTile = AnyOf<(Changed<Ground>, Changed<Plant>)>;
-type TileDisplay<'a> = (&'a Children, Entity, &'a Ground, &'a Plant);
+type ChangedTile = Or<(Changed<Ground>, Changed<Plant>)>;
+type TileDisplay<'a> = (
+    &'a Children,
+    Entity,
+    &'a Ground,
+    &'a Plant,
+    Option<&'a DisplayedPlant>,
+    Option<&'a Handle<Image>>,
+);
+
+/// The plant a tile's sprite currently shows, so changes can be animated from it.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct DisplayedPlant(pub Plant);
 
 fn display_tiles(
-    query: Query<TileDisplay, ChangedTile>,
+    changed: Query<TileDisplay, ChangedTile>,
+    all: Query<TileDisplay>,
     mut commands: Commands,
     assets: Res<GameAssets>,
     plants: Res<PlantDefinitions>,
+    settings: Res<Settings>,
 ) {
-    for (child, entity, backing, cell) in query.iter() {
-        let (soil, cell) = get_tile_image(backing, cell, assets.as_ref(), plants.as_ref());
-        commands.entity(entity).insert(soil);
-        if let Some(child) = child.first() {
-            if let Some(cell) = cell {
-                commands.entity(*child).insert((
-                    cell.0,
-                    Sprite {
-                        color: cell.1,
-                        custom_size: Some(TILE_WORLD_SIZE * Vec2::ONE),
-                        ..Default::default()
+    // Switching palettes means every tile needs redrawing, not just the ones that changed.
+    let tiles = if settings.is_changed() {
+        all.iter().collect::<Vec<_>>()
+    } else {
+        changed.iter().collect::<Vec<_>>()
+    };
+
+    for (child, entity, backing, cell, displayed, texture) in tiles {
+        let (soil, image) = get_tile_image(
+            backing,
+            cell,
+            assets.as_ref(),
+            plants.as_ref(),
+            settings.plant_palette,
+        );
+        let previous = displayed.map(|d| &d.0);
+        let plant_changed = previous != Some(cell);
+        if !plant_changed && texture == Some(&soil) && !settings.is_changed() {
+            continue;
+        }
+
+        // Tiles that are only just being drawn appear straight away.
+        let animate = !settings.reduced_motion && displayed.is_some();
+        if let Some(old) = texture.filter(|old| animate && **old != soil) {
+            commands.entity(entity).with_children(|p| {
+                p.spawn((
+                    SpriteBundle {
+                        texture: old.clone(),
+                        sprite: Sprite {
+                            custom_size: Some(TILE_WORLD_SIZE * Vec2::ONE),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(Vec3::Z * 0.5),
+                        ..default()
                     },
-                    Visibility::Visible,
+                    Tween::new(TweenKind::FadeOut),
                 ));
+            });
+        }
+        commands
+            .entity(entity)
+            .insert((soil, DisplayedPlant(cell.clone())));
+
+        let Some(child) = child.first() else { continue; };
+        let mut child = commands.entity(*child);
+        if let Some(image) = image {
+            let grow = animate && plant_changed && matches!(cell, Plant::Plant(_));
+            let scale = if grow { Vec3::ZERO } else { Vec3::ONE };
+            child.insert((
+                image.0,
+                Sprite {
+                    color: image.1,
+                    custom_size: Some(TILE_WORLD_SIZE * Vec2::ONE),
+                    ..Default::default()
+                },
+                Transform::from_translation(Vec3::Z).with_scale(scale),
+                Visibility::Visible,
+            ));
+            if grow {
+                child.insert(Tween::new(TweenKind::Grow));
+            } else {
+                child.remove::<Tween>();
+            }
+            if matches!(cell, Plant::Fire(_)) && !settings.reduced_motion {
+                child.insert(Flicker);
+            } else {
+                child.remove::<Flicker>();
+            }
+        } else {
+            child.remove::<Flicker>();
+            if animate && plant_changed && !matches!(previous, Some(Plant::Empty)) {
+                child.insert(Tween::new(TweenKind::Wilt));
             } else {
-                commands.entity(*child).insert(Visibility::Hidden);
+                child.remove::<Tween>().insert(Visibility::Hidden);
             }
         }
     }
@@ -49,6 +124,7 @@ fn get_tile_image(
     cell: &Plant,
     assets: &GameAssets,
     plants: &PlantDefinitions,
+    palette: PlantPalette,
 ) -> (Handle<Image>, Option<(Handle<Image>, Color)>) {
     (
         match backing {
@@ -66,7 +142,7 @@ fn get_tile_image(
             Plant::Plant(p) => plants
                 .assets
                 .get(p.as_str())
-                .map(|TileAsset(asset, c)| (asset.clone(), *c)),
+                .map(|asset| (asset.0.clone(), plants.color(p, palette))),
             Plant::Fire(remaining) => Some((
                 assets.fire.clone(),
                 Color::rgba(
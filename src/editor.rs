@@ -0,0 +1,663 @@
+use belly::{core::ess::Styles, prelude::*};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    assets::GameAssets,
+    bindings::{action_just_pressed, Controls},
+    control::Action,
+    hint::DEFAULT_HINTS,
+    level_asset::{CurrentLevel, LevelAsset, LevelList, LevelTiles},
+    locale::Strings,
+    pointer::world_to_tile,
+    states::AppState,
+    target::Reward,
+    tile::{
+        GameEntity, Ground, Plant, PlantDefinitions, Tile, TileAsset, FIRE_DURATION,
+        TILE_WORLD_SIZE,
+    },
+};
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorLevel>()
+            .init_resource::<EditorState>()
+            .init_resource::<OpeningLevel>()
+            .add_event::<EditorEvent>()
+            .add_system(
+                setup_menu
+                    .in_base_set(CoreSet::PreUpdate)
+                    .run_if(in_state(AppState::Editor)),
+            )
+            .add_system(handle_events.in_set(OnUpdate(AppState::Editor)))
+            .add_system(open_level.in_set(OnUpdate(AppState::Editor)))
+            .add_system(paint.in_set(OnUpdate(AppState::Editor)))
+            .add_system(draw_board.in_set(OnUpdate(AppState::Editor)))
+            .add_system(
+                playtest_key.run_if(
+                    in_state(AppState::Editor).and_then(action_just_pressed(Action::PlayTest)),
+                ),
+            )
+            .add_system(
+                end_playtest.run_if(
+                    in_state(AppState::InGame).and_then(action_just_pressed(Action::PlayTest)),
+                ),
+            )
+            .add_system(end_playtest.in_schedule(OnEnter(AppState::Menu)))
+            .add_system(end_playtest.in_schedule(OnEnter(AppState::LevelComplete)))
+            .add_system(end_playtest.in_schedule(OnEnter(AppState::LevelFailed)))
+            .add_system(clear_editor.in_schedule(OnExit(AppState::Editor)));
+    }
+}
+
+const NEW_LEVEL_FILE: &str = "custom_level.lvl.yaml";
+const MAX_LEVEL_SIZE: usize = 30;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditorTile {
+    pub ground: Ground,
+    pub plant: Plant,
+    pub entities: Vec<GameEntity>,
+}
+
+/// The level being edited, stored as rows of tiles in the same order as the text grid.
+#[derive(Resource, Clone)]
+pub struct EditorLevel {
+    pub file: String,
+    pub asset: LevelAsset,
+    pub rows: Vec<Vec<EditorTile>>,
+}
+
+impl Default for EditorLevel {
+    fn default() -> Self {
+        Self::blank(7, 5)
+    }
+}
+
+impl EditorLevel {
+    pub fn blank(width: usize, height: usize) -> Self {
+        let tile = EditorTile {
+            ground: Ground::Soil(false),
+            ..default()
+        };
+        Self {
+            file: NEW_LEVEL_FILE.to_string(),
+            asset: LevelAsset {
+                name: "Custom Level".to_string(),
+                hints: DEFAULT_HINTS,
+                ..default()
+            },
+            rows: vec![vec![tile; width]; height],
+        }
+    }
+
+    pub fn from_asset(file: String, asset: &LevelAsset) -> Self {
+        let tiles = &asset.tiles.0;
+        let min_x = tiles.keys().map(|t| t.0).min().unwrap_or_default();
+        let max_x = tiles.keys().map(|t| t.0).max().unwrap_or_default();
+        let min_y = tiles.keys().map(|t| t.1).min().unwrap_or_default();
+        let max_y = tiles.keys().map(|t| t.1).max().unwrap_or_default();
+
+        let rows = (min_y..=max_y)
+            .rev()
+            .map(|y| {
+                (min_x..=max_x)
+                    .map(|x| {
+                        tiles
+                            .get(&Tile(x, y))
+                            .map(|(ground, plant, entities)| EditorTile {
+                                ground: *ground,
+                                plant: plant.clone(),
+                                entities: entities.clone(),
+                            })
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            file,
+            asset: asset.clone(),
+            rows,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.first().map(|row| row.len()).unwrap_or_default()
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The same centering `LevelTiles::from_str` applies to the text grid.
+    fn offset(&self) -> (i8, i8) {
+        (
+            self.width().saturating_sub(1) as i8 / 2,
+            self.height().saturating_sub(1) as i8 / 2,
+        )
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> Tile {
+        let (x_offset, y_offset) = self.offset();
+        Tile(x as i8 - x_offset, y_offset - y as i8)
+    }
+
+    /// The extent of the grid, in tiles.
+    pub fn bounds(&self) -> Rect {
+        let corner = |x, y| {
+            let tile = self.tile(x, y);
+            Vec2::new(tile.0 as f32, tile.1 as f32)
+        };
+        let (width, height) = (self.width().max(1), self.height().max(1));
+        Rect::from_corners(corner(0, 0), corner(width - 1, height - 1))
+    }
+
+    pub fn position(&self, tile: Tile) -> Option<(usize, usize)> {
+        let (x_offset, y_offset) = self.offset();
+        let x = usize::try_from(tile.0 + x_offset).ok()?;
+        let y = usize::try_from(y_offset - tile.1).ok()?;
+        (x < self.width() && y < self.height()).then_some((x, y))
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let width = width.clamp(1, MAX_LEVEL_SIZE);
+        let height = height.clamp(1, MAX_LEVEL_SIZE);
+        let tile = EditorTile {
+            ground: Ground::Soil(false),
+            ..default()
+        };
+        for row in self.rows.iter_mut() {
+            row.resize(width, tile.clone());
+        }
+        self.rows.resize(height, vec![tile; width]);
+    }
+
+    /// Applies the brush to a tile, returning whether anything changed.
+    pub fn paint(&mut self, x: usize, y: usize, brush: &Brush, target_plant: &str) -> bool {
+        if matches!(brush, Brush::Player) {
+            for row in self.rows.iter_mut() {
+                for tile in row.iter_mut() {
+                    tile.entities.retain(|e| *e != GameEntity::Player);
+                }
+            }
+        }
+
+        let Some(tile) = self.rows.get_mut(y).and_then(|row| row.get_mut(x)) else { return false; };
+        let before = tile.clone();
+        match brush {
+            Brush::Ground(ground) => tile.ground = *ground,
+            Brush::Plant(plant) => tile.plant = plant.clone(),
+            Brush::Player => tile.entities.push(GameEntity::Player),
+            Brush::Target(reward) => {
+                tile.entities.retain(|e| *e == GameEntity::Player);
+                tile.entities
+                    .push(GameEntity::Target(target_plant.to_string(), *reward));
+            }
+            Brush::Erase => {
+                tile.plant = Plant::Empty;
+                tile.entities.clear();
+            }
+        }
+        *tile != before
+    }
+
+    /// The tiles as they'll be loaded - plants leave the ground under them fertile.
+    pub fn to_tiles(&self) -> LevelTiles {
+        let tiles = self.rows.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, tile)| {
+                let ground = tile.ground.under(&tile.plant);
+                let content = (ground, tile.plant.clone(), tile.entities.clone());
+                (self.tile(x, y), content)
+            })
+        });
+        LevelTiles(tiles.collect())
+    }
+
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(&LevelAsset {
+            tiles: self.to_tiles(),
+            ..self.asset.clone()
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Brush {
+    Ground(Ground),
+    Plant(Plant),
+    Player,
+    Target(Reward),
+    Erase,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self::Ground(Ground::Soil(false))
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct EditorState {
+    pub brush: Brush,
+    /// The plant targets are placed with - the last plant picked from the palette.
+    pub target_plant: String,
+    pub status: String,
+    pub playtesting: bool,
+}
+
+/// A level from the level list that is still loading before it can be edited.
+#[derive(Resource, Debug, Clone, Default)]
+struct OpeningLevel(Option<(String, Handle<LevelAsset>)>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum EditorEvent {
+    Brush(Brush),
+    Resize(i8, i8),
+    New,
+    Open(String),
+    Save,
+    PlayTest,
+}
+
+#[derive(Component)]
+struct MenuItem;
+
+#[derive(Component)]
+struct EditorBoard;
+
+#[allow(clippy::too_many_arguments)]
+fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    state: Res<EditorState>,
+    level: Res<EditorLevel>,
+    plants: Res<PlantDefinitions>,
+    level_list: Res<Assets<LevelList>>,
+    strings: Res<Strings>,
+    controls: Res<Controls>,
+    query: Query<Entity, With<MenuItem>>,
+    mut shown_size: Local<(usize, usize)>,
+) {
+    let size = (level.width(), level.height());
+    if !query.is_empty()
+        && !state.is_changed()
+        && !strings.is_changed()
+        && !controls.is_changed()
+        && *shown_size == size
+    {
+        return;
+    }
+    *shown_size = size;
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    styles.insert(assets.ui_style.clone());
+    let ui = commands.spawn(MenuItem).id();
+
+    let brush = |label: &str, brush: Brush| {
+        let class = if state.brush == brush {
+            "editor_button selected"
+        } else {
+            "editor_button"
+        };
+        (label.to_string(), brush, class.to_string())
+    };
+
+    let grounds = [
+        Ground::Empty,
+        Ground::Water,
+        Ground::Soil(false),
+        Ground::Soil(true),
+        Ground::Sand(false),
+        Ground::Sand(true),
+        Ground::Rock(false),
+        Ground::Rock(true),
+    ]
+    .into_iter()
+    .map(|ground| brush(&strings.get(ground.label_key()), Brush::Ground(ground)))
+    .collect::<Vec<_>>();
+
+    let plant_brushes = plants
+        .definitions
+        .iter()
+        .map(|p| (plants.name(&p.id), Brush::Plant(Plant::Plant(p.id.clone()))))
+        .chain([
+            (
+                strings.get("editor.fire"),
+                Brush::Plant(Plant::Fire(FIRE_DURATION)),
+            ),
+            (strings.get("editor.no_plant"), Brush::Plant(Plant::Empty)),
+        ])
+        .map(|(label, b)| brush(&label, b))
+        .collect::<Vec<_>>();
+
+    let entities = [
+        ("editor.player", Brush::Player),
+        ("editor.goal", Brush::Target(Reward::CompleteLevel)),
+        ("editor.fertilize_shrine", Brush::Target(Reward::Fertilize)),
+        ("editor.fire_shrine", Brush::Target(Reward::Burn)),
+        ("editor.seed_shrine", Brush::Target(Reward::Seed)),
+        ("editor.drain_shrine", Brush::Target(Reward::Drain)),
+        ("editor.swim_shrine", Brush::Target(Reward::Swim)),
+        ("editor.float_shrine", Brush::Target(Reward::Float)),
+        ("editor.climb_shrine", Brush::Target(Reward::Climb)),
+        ("editor.erase", Brush::Erase),
+    ]
+    .into_iter()
+    .map(|(key, b)| brush(&strings.get(key), b))
+    .collect::<Vec<_>>();
+
+    let levels = level_list
+        .get(&assets.levels)
+        .map(|list| list.levels())
+        .unwrap_or_default();
+
+    let title = strings.format("editor.title", &[("file", &level.file)]);
+    let size = strings.format("editor.size", &[("width", &size.0), ("height", &size.1)]);
+    let target_plant = strings.format(
+        "editor.targets_need",
+        &[("plant", &plants.name(&state.target_plant))],
+    );
+    let status = state.status.clone();
+    let ground = strings.get("editor.ground");
+    let plant = strings.get("editor.plants");
+    let entity = strings.get("editor.entities");
+    let open = strings.get("editor.open");
+    let wider = strings.get("editor.wider");
+    let narrower = strings.get("editor.narrower");
+    let taller = strings.get("editor.taller");
+    let shorter = strings.get("editor.shorter");
+    let new = strings.get("editor.new");
+    let play_test = strings.format(
+        "editor.play_test",
+        &[("key", &controls.label(Action::PlayTest))],
+    );
+    let save = strings.get("editor.save");
+    let menu = strings.get("common.menu");
+
+    let root = strings.root_class("editor");
+    commands.add(eml! {
+        <body {ui} class={root}>
+            <div c:editor_panel>
+                <div c:level_header>{title}</div>
+                <div c:editor_section>{ground}</div>
+                <div c:editor_palette>
+                    <for item in=grounds>
+                        <button class={item.2.clone()} on:press=move |ctx| ctx.send_event(EditorEvent::Brush(item.1.clone()))>
+                            <span c:content>{item.0.clone()}</span>
+                        </button>
+                    </for>
+                </div>
+                <div c:editor_section>{plant}</div>
+                <div c:editor_palette>
+                    <for item in=plant_brushes>
+                        <button class={item.2.clone()} on:press=move |ctx| ctx.send_event(EditorEvent::Brush(item.1.clone()))>
+                            <span c:content>{item.0.clone()}</span>
+                        </button>
+                    </for>
+                </div>
+                <div c:editor_section>{entity}</div>
+                <div c:editor_status>{target_plant}</div>
+                <div c:editor_palette>
+                    <for item in=entities>
+                        <button class={item.2.clone()} on:press=move |ctx| ctx.send_event(EditorEvent::Brush(item.1.clone()))>
+                            <span c:content>{item.0.clone()}</span>
+                        </button>
+                    </for>
+                </div>
+                <div c:editor_section>{size}</div>
+                <div c:editor_palette>
+                    <button c:editor_button on:press=|ctx| ctx.send_event(EditorEvent::Resize(1, 0))><span c:content>{wider}</span></button>
+                    <button c:editor_button on:press=|ctx| ctx.send_event(EditorEvent::Resize(-1, 0))><span c:content>{narrower}</span></button>
+                    <button c:editor_button on:press=|ctx| ctx.send_event(EditorEvent::Resize(0, 1))><span c:content>{taller}</span></button>
+                    <button c:editor_button on:press=|ctx| ctx.send_event(EditorEvent::Resize(0, -1))><span c:content>{shorter}</span></button>
+                </div>
+                <div c:editor_section>{open}</div>
+                <div c:editor_palette>
+                    <button c:editor_button on:press=|ctx| ctx.send_event(EditorEvent::New)><span c:content>{new}</span></button>
+                    <for level in=levels>
+                        <button c:editor_button on:press=move |ctx| ctx.send_event(EditorEvent::Open(level.clone()))>
+                            <span c:content>{level.replace(".lvl.yaml", "").replace('_', " ")}</span>
+                        </button>
+                    </for>
+                </div>
+                <div c:editor_palette>
+                    <button c:exit_button on:press=|ctx| ctx.send_event(EditorEvent::PlayTest)><span c:content>{play_test}</span></button>
+                    <button c:exit_button on:press=|ctx| ctx.send_event(EditorEvent::Save)><span c:content>{save}</span></button>
+                    <button c:exit_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Menu)))><span c:content>{menu}</span></button>
+                </div>
+                <div c:editor_status>{status}</div>
+            </div>
+        </body>
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_events(
+    mut events: EventReader<EditorEvent>,
+    mut state: ResMut<EditorState>,
+    mut level: ResMut<EditorLevel>,
+    mut opening: ResMut<OpeningLevel>,
+    mut level_assets: ResMut<Assets<LevelAsset>>,
+    server: Res<AssetServer>,
+    strings: Res<Strings>,
+    mut commands: Commands,
+) {
+    for event in events.iter() {
+        match event {
+            EditorEvent::Brush(brush) => {
+                if let Brush::Plant(Plant::Plant(id)) = brush {
+                    state.target_plant = id.clone();
+                }
+                state.brush = brush.clone();
+            }
+            EditorEvent::Resize(width, height) => {
+                let width = (level.width() as i32 + *width as i32).max(1) as usize;
+                let height = (level.height() as i32 + *height as i32).max(1) as usize;
+                level.resize(width, height);
+            }
+            EditorEvent::New => {
+                *level = EditorLevel::default();
+                state.status = strings.get("editor.started");
+            }
+            EditorEvent::Open(file) => {
+                let handle = server.load(format!("levels/{file}"));
+                opening.0 = Some((file.clone(), handle));
+                state.status = strings.format("editor.opening", &[("file", file)]);
+            }
+            EditorEvent::Save => {
+                state.status = save_level(&level, &strings);
+            }
+            EditorEvent::PlayTest => {
+                let mut asset = level.asset.clone();
+                asset.tiles = level.to_tiles();
+                let handle = level_assets.add(asset);
+                commands.insert_resource(CurrentLevel(Some(handle)));
+                commands.insert_resource(NextState(Some(AppState::InGame)));
+                state.playtesting = true;
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_level(level: &EditorLevel, strings: &Strings) -> String {
+    let path = std::path::Path::new("assets/levels").join(&level.file);
+    match level
+        .to_yaml()
+        .map_err(anyhow::Error::from)
+        .and_then(|yaml| std::fs::write(&path, format!("---\n{yaml}")).map_err(anyhow::Error::from))
+    {
+        Ok(_) => strings.format("editor.saved", &[("path", &path.display())]),
+        Err(e) => strings.format(
+            "editor.save_failed",
+            &[("path", &path.display()), ("error", &e)],
+        ),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_level(_level: &EditorLevel, strings: &Strings) -> String {
+    strings.get("editor.save_unavailable")
+}
+
+fn open_level(
+    mut opening: ResMut<OpeningLevel>,
+    level_assets: Res<Assets<LevelAsset>>,
+    mut level: ResMut<EditorLevel>,
+    mut state: ResMut<EditorState>,
+    strings: Res<Strings>,
+) {
+    let Some((file, handle)) = opening.0.as_ref() else { return; };
+    let Some(asset) = level_assets.get(handle) else { return; };
+    *level = EditorLevel::from_asset(file.clone(), asset);
+    state.status = strings.format("editor.opened", &[("file", file)]);
+    opening.0 = None;
+}
+
+fn playtest_key(mut events: EventWriter<EditorEvent>) {
+    events.send(EditorEvent::PlayTest);
+}
+
+fn end_playtest(mut state: ResMut<EditorState>, mut commands: Commands) {
+    if !state.playtesting {
+        return;
+    }
+    state.playtesting = false;
+    commands.insert_resource(NextState(Some(AppState::Editor)));
+}
+
+fn paint(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mouse: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    interactions: Query<&Interaction>,
+    state: Res<EditorState>,
+    mut level: ResMut<EditorLevel>,
+) {
+    let Ok(window) = windows.get_single() else { return; };
+    let Ok((camera, camera_transform)) = cameras.get_single() else { return; };
+
+    let position = if mouse.pressed(MouseButton::Left) {
+        window.cursor_position()
+    } else {
+        touches.iter().next().map(|touch| touch.position())
+    };
+    let Some(position) = position else { return; };
+
+    if interactions.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+
+    let Some(position) = camera.viewport_to_world_2d(camera_transform, position) else { return; };
+    let Some((x, y)) = level.position(world_to_tile(position)) else { return; };
+
+    // Only flag a change when the tile actually changed, so the board isn't rebuilt every frame.
+    if level
+        .bypass_change_detection()
+        .paint(x, y, &state.brush, &state.target_plant)
+    {
+        level.set_changed();
+    }
+}
+
+fn draw_board(
+    mut commands: Commands,
+    level: Res<EditorLevel>,
+    boards: Query<Entity, With<EditorBoard>>,
+    assets: Res<GameAssets>,
+    plants: Res<PlantDefinitions>,
+) {
+    if !level.is_changed() && !boards.is_empty() {
+        return;
+    }
+
+    for entity in boards.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands
+        .spawn((SpatialBundle::default(), EditorBoard))
+        .with_children(|p| {
+            for (y, row) in level.rows.iter().enumerate() {
+                for (x, editor_tile) in row.iter().enumerate() {
+                    let tile = level.tile(x, y);
+                    let translation = Vec3::new(tile.0 as f32, tile.1 as f32, 0.) * TILE_WORLD_SIZE;
+                    p.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                custom_size: Some(Vec2::new(TILE_WORLD_SIZE, TILE_WORLD_SIZE)),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(translation),
+                            ..default()
+                        },
+                        editor_tile.ground,
+                        editor_tile.plant.clone(),
+                        tile,
+                    ))
+                    .with_children(|p| {
+                        p.spawn(SpriteBundle {
+                            transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+                            sprite: Sprite {
+                                custom_size: Some(Vec2::new(TILE_WORLD_SIZE, TILE_WORLD_SIZE)),
+                                ..default()
+                            },
+                            ..default()
+                        });
+                    });
+
+                    for entity in editor_tile.entities.iter() {
+                        let (texture, color, plant) = match entity {
+                            GameEntity::Player => (assets.player.clone(), Color::WHITE, None),
+                            GameEntity::Target(plant, reward) => (
+                                reward.image(&assets),
+                                Color::WHITE,
+                                plants.assets.get(plant),
+                            ),
+                        };
+                        p.spawn(SpriteBundle {
+                            sprite: Sprite {
+                                color,
+                                custom_size: Some(Vec2::new(TILE_WORLD_SIZE, TILE_WORLD_SIZE)),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(translation + Vec3::Z * 2.),
+                            texture,
+                            ..default()
+                        })
+                        .with_children(|p| {
+                            let Some(TileAsset(image, color)) = plant else { return; };
+                            p.spawn(SpriteBundle {
+                                sprite: Sprite {
+                                    color: *color,
+                                    custom_size: Some(TILE_WORLD_SIZE * Vec2::ONE * 0.3),
+                                    ..default()
+                                },
+                                transform: Transform::from_translation(Vec3::Z),
+                                texture: image.clone(),
+                                ..default()
+                            });
+                        });
+                    }
+                }
+            }
+        });
+}
+
+fn clear_editor(
+    mut commands: Commands,
+    menu: Query<Entity, With<MenuItem>>,
+    boards: Query<Entity, With<EditorBoard>>,
+) {
+    for entity in menu.iter().chain(boards.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
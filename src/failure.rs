@@ -0,0 +1,309 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    control::{AvailablePowers, Player, Power, Seed},
+    hazards::{DeathCause, PlayerEvent, PlayerRules},
+    history::PendingRestore,
+    level_asset::{CurrentLevel, LevelAsset},
+    locale::Strings,
+    movement::Abilities,
+    states::AppState,
+    target::{Reward, Target, UsedTarget},
+    tile::{Ground, Plant, PlantDefinitions, Tile},
+    update_tiles::is_settled,
+};
+
+pub struct FailurePlugin;
+
+impl Plugin for FailurePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(detect_failure.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                fail_on_death
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FailureReason {
+    /// The goal's plant, by its id, has died out.
+    TargetExtinct(String),
+    Stranded,
+    PlayerDied(DeathCause),
+}
+
+impl FailureReason {
+    pub fn describe(&self, plants: &PlantDefinitions, strings: &Strings) -> String {
+        match self {
+            FailureReason::TargetExtinct(plant) => {
+                strings.format("failure.extinct", &[("plant", &plants.name(plant))])
+            }
+            FailureReason::Stranded => strings.get("failure.stranded"),
+            FailureReason::PlayerDied(DeathCause::Burned) => strings.get("failure.burned"),
+            FailureReason::PlayerDied(DeathCause::Drowned) => strings.get("failure.drowned"),
+        }
+    }
+}
+
+/// The level that was just failed, and why.
+#[derive(Resource, Debug, Clone)]
+pub struct LevelFailure {
+    pub level: Handle<LevelAsset>,
+    pub reason: FailureReason,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn detect_failure(
+    tiles: Query<(&Tile, &Ground, &Plant)>,
+    changed_tiles: Query<(), Or<(Changed<Ground>, Changed<Plant>)>>,
+    players: Query<&Player>,
+    moved: Query<(), Changed<Player>>,
+    targets: Query<&Target, Without<UsedTarget>>,
+    powers: Res<AvailablePowers>,
+    seed: Res<Seed>,
+    abilities: Res<Abilities>,
+    plants: Res<PlantDefinitions>,
+    rules: Res<PlayerRules>,
+    current_level: Res<CurrentLevel>,
+    pending: Res<PendingRestore>,
+    next_state: Res<NextState<AppState>>,
+    mut commands: Commands,
+) {
+    if next_state.0.is_some() || pending.0.is_some() {
+        return;
+    }
+    // Nothing can have failed since the last check unless the board, the player or what they hold changed.
+    if changed_tiles.is_empty()
+        && moved.is_empty()
+        && !powers.is_changed()
+        && !seed.is_changed()
+        && !abilities.is_changed()
+    {
+        return;
+    }
+    let Some(level) = current_level.0.clone() else { return; };
+    let Ok(player) = players.get_single() else { return; };
+
+    let tiles = tiles
+        .iter()
+        .map(|(t, g, p)| (*t, (g, p)))
+        .collect::<HashMap<_, _>>();
+    if tiles.is_empty() {
+        return;
+    }
+
+    let reason = find_extinct_target(&tiles, targets.iter(), &powers, &seed).or_else(|| {
+        is_stranded(
+            &tiles,
+            Tile(player.0, player.1),
+            &powers,
+            &abilities,
+            &rules,
+            &plants,
+        )
+        .then_some(FailureReason::Stranded)
+    });
+
+    if let Some(reason) = reason {
+        info!("Level Failed: {reason:?}");
+        commands.insert_resource(LevelFailure { level, reason });
+        commands.insert_resource(NextState(Some(AppState::LevelFailed)));
+    }
+}
+
+fn fail_on_death(
+    mut events: EventReader<PlayerEvent>,
+    current_level: Res<CurrentLevel>,
+    mut commands: Commands,
+) {
+    let Some(level) = current_level.0.clone() else { return; };
+    for event in events.iter() {
+        if let PlayerEvent::Died(cause) = event {
+            let reason = FailureReason::PlayerDied(*cause);
+            info!("Level Failed: {reason:?}");
+            commands.insert_resource(LevelFailure {
+                level: level.clone(),
+                reason,
+            });
+            commands.insert_resource(NextState(Some(AppState::LevelFailed)));
+        }
+    }
+}
+
+/// Plants never appear from nothing, so once the plant a goal needs is gone
+/// from the board (and isn't held as a seed) the goal can never be reached.
+fn find_extinct_target<'a>(
+    tiles: &HashMap<Tile, (&Ground, &Plant)>,
+    mut targets: impl Iterator<Item = &'a Target>,
+    powers: &AvailablePowers,
+    seed: &Seed,
+) -> Option<FailureReason> {
+    targets
+        .find(|target| {
+            if target.2 != Reward::CompleteLevel {
+                return false;
+            }
+            let holding_seed = powers.0.contains_key(&Power::Plant)
+                && matches!(&seed.0, Some((id, _)) if *id == target.1);
+            let growing = tiles
+                .values()
+                .any(|(_, p)| matches!(p, Plant::Plant(id) if *id == target.1));
+            !holding_seed && !growing
+        })
+        .map(|target| FailureReason::TargetExtinct(target.1.clone()))
+}
+
+fn is_stranded(
+    tiles: &HashMap<Tile, (&Ground, &Plant)>,
+    player: Tile,
+    powers: &AvailablePowers,
+    abilities: &Abilities,
+    rules: &PlayerRules,
+    plants: &PlantDefinitions,
+) -> bool {
+    if !powers.0.is_empty() {
+        return false;
+    }
+    let can_move = !abilities.neighbours(player, tiles, rules).is_empty();
+
+    !can_move && is_settled(tiles, plants)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movement::DIRECTIONS;
+
+    fn plants() -> PlantDefinitions {
+        PlantDefinitions {
+            definitions: vec![],
+            name_to_id: default(),
+            assets: default(),
+            palette_index: default(),
+            language: "en".to_string(),
+        }
+    }
+
+    fn board(tiles: &[(Tile, Ground, Plant)]) -> HashMap<Tile, (&Ground, &Plant)> {
+        tiles.iter().map(|(t, g, p)| (*t, (g, p))).collect()
+    }
+
+    fn goal(plant: &str) -> Target {
+        Target(Tile(9, 9), plant.to_string(), Reward::CompleteLevel)
+    }
+
+    fn holding(plant: &str) -> (AvailablePowers, Seed) {
+        (
+            AvailablePowers([(Power::Plant, 1)].into_iter().collect()),
+            Seed(Some((plant.to_string(), format!("{plant}.png")))),
+        )
+    }
+
+    #[test]
+    fn a_goal_is_extinct_once_its_plant_is_gone() {
+        let tiles = vec![(Tile(0, 0), Ground::Soil(true), Plant::Plant("moss".into()))];
+        let targets = [goal("flower")];
+        let reason = find_extinct_target(
+            &board(&tiles),
+            targets.iter(),
+            &AvailablePowers::default(),
+            &Seed::default(),
+        );
+        assert_eq!(reason, Some(FailureReason::TargetExtinct("flower".into())));
+    }
+
+    #[test]
+    fn a_goal_isnt_extinct_while_its_plant_grows() {
+        let tiles = vec![(
+            Tile(0, 0),
+            Ground::Soil(true),
+            Plant::Plant("flower".into()),
+        )];
+        let targets = [goal("flower")];
+        let reason = find_extinct_target(
+            &board(&tiles),
+            targets.iter(),
+            &AvailablePowers::default(),
+            &Seed::default(),
+        );
+        assert_eq!(reason, None);
+    }
+
+    #[test]
+    fn a_held_seed_keeps_a_goal_alive() {
+        let tiles = vec![(Tile(0, 0), Ground::Soil(true), Plant::Empty)];
+        let targets = [goal("flower")];
+        let (powers, seed) = holding("flower");
+        let reason = find_extinct_target(&board(&tiles), targets.iter(), &powers, &seed);
+        assert_eq!(reason, None);
+
+        // A seed of another plant doesn't help.
+        let (powers, seed) = holding("moss");
+        let reason = find_extinct_target(&board(&tiles), targets.iter(), &powers, &seed);
+        assert_eq!(reason, Some(FailureReason::TargetExtinct("flower".into())));
+    }
+
+    #[test]
+    fn only_the_level_goal_can_go_extinct() {
+        let tiles = vec![(Tile(0, 0), Ground::Soil(true), Plant::Empty)];
+        let targets = [Target(Tile(9, 9), "flower".to_string(), Reward::Seed)];
+        let reason = find_extinct_target(
+            &board(&tiles),
+            targets.iter(),
+            &AvailablePowers::default(),
+            &Seed::default(),
+        );
+        assert_eq!(reason, None);
+    }
+
+    /// The player on bare soil, walled in by water they can't swim.
+    fn island(extra: Option<(Tile, Ground, Plant)>) -> Vec<(Tile, Ground, Plant)> {
+        let mut tiles = vec![(Tile(0, 0), Ground::Soil(false), Plant::Empty)];
+        for (x, y) in DIRECTIONS {
+            tiles.push((Tile(x, y), Ground::Water, Plant::Empty));
+        }
+        tiles.extend(extra);
+        tiles
+    }
+
+    fn stranded(tiles: &[(Tile, Ground, Plant)], powers: &AvailablePowers) -> bool {
+        is_stranded(
+            &board(tiles),
+            Tile(0, 0),
+            powers,
+            &Abilities::default(),
+            &PlayerRules::default(),
+            &plants(),
+        )
+    }
+
+    #[test]
+    fn stuck_on_a_settled_board_is_stranded() {
+        assert!(stranded(&island(None), &AvailablePowers::default()));
+    }
+
+    #[test]
+    fn stuck_while_the_board_still_changes_isnt_stranded() {
+        let burning = (Tile(5, 5), Ground::Soil(false), Plant::Fire(2));
+        assert!(!stranded(
+            &island(Some(burning)),
+            &AvailablePowers::default()
+        ));
+    }
+
+    #[test]
+    fn a_power_left_to_use_isnt_stranded() {
+        let (powers, _) = holding("flower");
+        assert!(!stranded(&island(None), &powers));
+    }
+
+    #[test]
+    fn a_way_out_isnt_stranded() {
+        let mut tiles = island(None);
+        tiles[1].2 = Plant::Plant("moss".into());
+        assert!(!stranded(&tiles, &AvailablePowers::default()));
+    }
+}
@@ -0,0 +1,136 @@
+//! Symbols drawn over the tiles when turned on in the settings, so plants and fertile ground
+//! can be told apart without relying on colour.
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    assets::GameAssets,
+    colors::Palette,
+    settings::Settings,
+    states::AppState,
+    tile::{Ground, Plant, PlantDefinitions, TILE_WORLD_SIZE},
+};
+
+pub struct GlyphPlugin;
+
+impl Plugin for GlyphPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_glyphs.in_set(OnUpdate(AppState::InGame)))
+            .add_system(spawn_glyphs.in_set(OnUpdate(AppState::Editor)))
+            .add_system(
+                update_glyphs
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(spawn_glyphs),
+            )
+            .add_system(
+                update_glyphs
+                    .in_set(OnUpdate(AppState::Editor))
+                    .after(spawn_glyphs),
+            );
+    }
+}
+
+const PLANT_GLYPH_SIZE: f32 = 28.;
+const GROUND_GLYPH_SIZE: f32 = 20.;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum TileGlyph {
+    Plant,
+    Ground,
+}
+
+/// Marks a tile that already has its glyphs spawned.
+#[derive(Component)]
+struct HasGlyphs;
+
+fn ground_glyph(ground: &Ground) -> &'static str {
+    match ground {
+        Ground::Soil(true) | Ground::Sand(true) | Ground::Rock(true) => "+",
+        Ground::Soil(false) | Ground::Sand(false) | Ground::Rock(false) => "-",
+        Ground::Water => "~",
+        Ground::Empty => "",
+    }
+}
+
+fn plant_glyph(plant: &Plant, plants: &PlantDefinitions) -> String {
+    match plant {
+        Plant::Empty => String::new(),
+        Plant::Plant(id) => plants.glyph(id),
+        Plant::Fire(_) => "!".to_string(),
+    }
+}
+
+fn spawn_glyphs(
+    mut commands: Commands,
+    tiles: Query<Entity, (With<Ground>, With<Plant>, Without<HasGlyphs>)>,
+    assets: Res<GameAssets>,
+) {
+    for entity in tiles.iter() {
+        let text = |glyph: TileGlyph, size: f32, anchor: Anchor, offset: Vec3| {
+            (
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: assets.font.clone(),
+                            font_size: size,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    text_anchor: anchor,
+                    transform: Transform::from_translation(offset),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                glyph,
+            )
+        };
+        let corner = TILE_WORLD_SIZE * 0.45;
+        commands
+            .entity(entity)
+            .insert(HasGlyphs)
+            .with_children(|p| {
+                p.spawn(text(
+                    TileGlyph::Plant,
+                    PLANT_GLYPH_SIZE,
+                    Anchor::Center,
+                    Vec3::Z * 3.,
+                ));
+                p.spawn(text(
+                    TileGlyph::Ground,
+                    GROUND_GLYPH_SIZE,
+                    Anchor::BottomRight,
+                    Vec3::new(corner, -corner, 3.),
+                ));
+            });
+    }
+}
+
+fn update_glyphs(
+    tiles: Query<(&Children, Ref<Ground>, Ref<Plant>), With<HasGlyphs>>,
+    mut glyphs: Query<(Ref<TileGlyph>, &mut Text, &mut Visibility)>,
+    plants: Res<PlantDefinitions>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+) {
+    for (children, ground, plant) in tiles.iter() {
+        let changed = settings.is_changed() || ground.is_changed() || plant.is_changed();
+        for child in children.iter() {
+            let Ok((glyph, mut text, mut visibility)) = glyphs.get_mut(*child) else { continue; };
+            if !changed && !glyph.is_added() {
+                continue;
+            }
+            *visibility = if settings.tile_glyphs {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            let Some(section) = text.sections.first_mut() else { continue; };
+            section.value = match *glyph {
+                TileGlyph::Plant => plant_glyph(&plant, &plants),
+                TileGlyph::Ground => ground_glyph(&ground).to_string(),
+            };
+            section.style.color = palette.pale;
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    control::Player,
+    generate_tiles::LevelLoaded,
+    level_asset::{CurrentLevel, LevelAsset},
+    movement::{Abilities, Ability},
+    states::AppState,
+    tile::{Ground, Plant, Tile},
+};
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerRules>()
+            .add_event::<PlayerEvent>()
+            .add_system(load_player_rules.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(
+                load_player_rules
+                    .run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            )
+            .add_system(setup_health.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                apply_hazards
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// How the tile under the player affects them. Set per level under `player_rules`.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerRules {
+    pub health: usize,
+    /// Health lost each generation the player spends standing in fire. 0 makes fire harmless.
+    pub fire_damage: usize,
+    /// When the player's own tile is bare, they may only step onto living plants - not fire.
+    /// Off by default, so levels that don't ask for it move the way they always have.
+    pub stranded_living_only: bool,
+    /// The player drowns if the plant holding them up on a water tile disappears,
+    /// unless they have learned to swim.
+    pub drown: bool,
+}
+
+impl Default for PlayerRules {
+    fn default() -> Self {
+        Self {
+            health: 3,
+            fire_damage: 1,
+            stranded_living_only: false,
+            drown: false,
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Health(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeathCause {
+    Burned,
+    Drowned,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayerEvent {
+    Burned { health: usize },
+    Died(DeathCause),
+}
+
+fn load_player_rules(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<LevelAsset>>,
+) {
+    let rules = current_level
+        .0
+        .as_ref()
+        .and_then(|level| level_assets.get(level))
+        .map(|level| level.player_rules.clone())
+        .unwrap_or_default();
+    commands.insert_resource(rules);
+}
+
+fn setup_health(
+    mut commands: Commands,
+    players: Query<Entity, (With<Player>, Without<Health>)>,
+    rules: Res<PlayerRules>,
+) {
+    for e in players.iter() {
+        commands.entity(e).insert(Health(rules.health));
+    }
+}
+
+fn apply_hazards(
+    mut players: Query<(Ref<Player>, &mut Health)>,
+    tiles: Query<(&Tile, Ref<Ground>, Ref<Plant>)>,
+    rules: Res<PlayerRules>,
+    abilities: Res<Abilities>,
+    mut events: EventWriter<PlayerEvent>,
+) {
+    let Ok((player, mut health)) = players.get_single_mut() else { return; };
+    if health.0 == 0 {
+        return;
+    }
+    let position = Tile(player.0, player.1);
+    let Some((_, ground, plant)) = tiles.iter().find(|(t, _, _)| **t == position) else { return; };
+    if !player.is_changed() && !ground.is_changed() && !plant.is_changed() {
+        return;
+    }
+
+    match &*plant {
+        Plant::Fire(_) if rules.fire_damage > 0 => {
+            health.0 = health.0.saturating_sub(rules.fire_damage);
+            events.send(PlayerEvent::Burned { health: health.0 });
+            if health.0 == 0 {
+                events.send(PlayerEvent::Died(DeathCause::Burned));
+            }
+        }
+        Plant::Empty
+            if rules.drown && *ground == Ground::Water && !abilities.has(Ability::Swim) =>
+        {
+            health.0 = 0;
+            events.send(PlayerEvent::Died(DeathCause::Drowned));
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,154 @@
+//! Runs the simulation without a window, renderer or audio, so replays can be checked from
+//! the command line with `--headless --replay <file>`, and from tests.
+
+use std::time::{Duration, Instant};
+
+use bevy::{
+    ecs::event::ManualEventReader, input::InputPlugin, log::LogPlugin, prelude::*,
+    time::TimeUpdateStrategy,
+};
+
+use crate::{
+    assets::GameAssets,
+    bindings::Controls,
+    clock::{ClockPlugin, GENERATION_SECONDS},
+    control::ControlPlugin,
+    failure::FailurePlugin,
+    generate_tiles::TileGeneratorPlugin,
+    hazards::HazardPlugin,
+    history::HistoryPlugin,
+    level_asset::LevelAssetPlugin,
+    level_loading_screen::enter_level,
+    locale::Strings,
+    movement::MovementPlugin,
+    replay::{PendingReplay, PlaybackFinished, ReplayPlugin},
+    settings::Settings,
+    states::AppState,
+    target::TargetPlugin,
+    tile::{PlantDefinitions, TilePlugin},
+    update_tiles::UpdateTilesPlugin,
+};
+
+/// The bare app plus just the plugins that play a level - the player, tiles, targets and hazards.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(InputPlugin)
+            .add_state::<AppState>()
+            // Every frame is a whole generation, so a replay runs as fast as it can be simulated.
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                GENERATION_SECONDS,
+            )))
+            .init_resource::<Settings>()
+            .init_resource::<Controls>()
+            .init_resource::<Strings>()
+            .add_startup_system(load_assets)
+            .add_system(wait_for_assets.in_set(OnUpdate(AppState::LoadingAssets)))
+            .add_system(enter_level.in_set(OnUpdate(AppState::LoadingLevel)))
+            .add_plugin(LevelAssetPlugin)
+            .add_plugin(TilePlugin)
+            .add_plugin(TileGeneratorPlugin)
+            .add_plugin(UpdateTilesPlugin)
+            .add_plugin(ClockPlugin)
+            .add_plugin(ControlPlugin)
+            .add_plugin(MovementPlugin)
+            .add_plugin(TargetPlugin)
+            .add_plugin(HazardPlugin)
+            .add_plugin(HistoryPlugin)
+            .add_plugin(FailurePlugin)
+            .add_plugin(ReplayPlugin);
+    }
+}
+
+/// How long a replay gets to finish before it is given up on.
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn requested() -> bool {
+    std::env::args().any(|arg| arg == "--headless")
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn requested() -> bool {
+    false
+}
+
+/// Plays the replay given with `--replay`, returning the code to exit with.
+pub fn run_from_args() -> i32 {
+    let mut app = App::new();
+    app.add_plugin(LogPlugin::default())
+        .add_plugin(HeadlessPlugin);
+    if app.world.resource::<PendingReplay>().0.is_none() {
+        error!("Nothing to play - pass a replay with --replay <file>");
+        return 2;
+    }
+    match run_replay(&mut app) {
+        Some(true) => 0,
+        Some(false) => 1,
+        None => {
+            error!("The replay didn't finish within {REPLAY_TIMEOUT:?}");
+            1
+        }
+    }
+}
+
+/// Updates the app until its replay finishes, returning whether the level ended the way it was recorded.
+pub fn run_replay(app: &mut App) -> Option<bool> {
+    let mut finished = ManualEventReader::<PlaybackFinished>::default();
+    let started = Instant::now();
+    while started.elapsed() < REPLAY_TIMEOUT {
+        app.update();
+        let events = app.world.resource::<Events<PlaybackFinished>>();
+        if let Some(event) = finished.iter(events).last() {
+            return Some(event.matched);
+        }
+    }
+    None
+}
+
+/// Only the plant definitions are needed to play a level, so they're the only assets loaded.
+fn load_assets(server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(GameAssets {
+        plants: server.load("plants.pdef.json"),
+        ..default()
+    });
+}
+
+fn wait_for_assets(plants: Option<Res<PlantDefinitions>>, mut commands: Commands) {
+    if plants.is_some() {
+        commands.insert_resource(NextState(Some(AppState::Menu)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{Replay, ReplayOutcome};
+
+    fn fixture() -> Replay {
+        serde_json::from_str(include_str!("../tests/replays/two_steps.json"))
+            .expect("invalid replay")
+    }
+
+    fn play(replay: Replay) -> Option<bool> {
+        let mut app = App::new();
+        app.add_plugin(HeadlessPlugin)
+            .insert_resource(PendingReplay(Some((replay, true))));
+        run_replay(&mut app)
+    }
+
+    #[test]
+    fn replay_ends_as_recorded() {
+        assert_eq!(play(fixture()), Some(true));
+    }
+
+    #[test]
+    fn replay_with_a_different_ending_is_caught() {
+        let mut replay = fixture();
+        replay.outcome = Some(ReplayOutcome::Completed { generation: 3 });
+        assert_eq!(play(replay), Some(false));
+    }
+}
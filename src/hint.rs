@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use bevy_vector_shapes::{prelude::ShapePainter, shapes::DiscPainter};
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    colors::Palette,
+    control::{Action, Player},
+    generate_tiles::LevelLoaded,
+    hazards::PlayerRules,
+    history::{LevelState, PendingRestore},
+    level_asset::{CurrentLevel, LevelAsset},
+    locale::Strings,
+    progress::LevelStats,
+    settings::Settings,
+    solver::{Puzzle, Search, SearchResult, SolverAction},
+    states::AppState,
+    target::Target,
+    tile::{PlantDefinitions, Tile, TILE_WORLD_SIZE},
+    update_tiles::Generation,
+};
+
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hints>()
+            .add_event::<RequestHint>()
+            .add_system(reset_hints.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(
+                reset_hints.run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            )
+            .add_system(clear_hint.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                give_hint
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(clear_hint),
+            )
+            .add_system(
+                finish_hint
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(give_hint),
+            )
+            .add_system(draw_hint.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+/// How many hints a level allows when it doesn't set its own limit.
+pub const DEFAULT_HINTS: usize = 3;
+
+/// The hints left for the current level, and the one being shown.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Hints {
+    pub remaining: usize,
+    pub current: Option<SolverAction>,
+    pub message: Option<String>,
+}
+
+/// Sent by the Hint button to work out and show the next step towards completing the level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestHint;
+
+/// How many states the hint search looks at each frame, so a hard level doesn't stall the game.
+const STATES_PER_FRAME: usize = 200;
+
+/// The search for the next step, run a little each frame until it finishes.
+#[derive(Resource)]
+struct HintSearch(Search);
+
+/// Refills the hints for a fresh level. Undo and resume keep the hints that were left.
+fn reset_hints(
+    mut hints: ResMut<Hints>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<LevelAsset>>,
+    pending: Res<PendingRestore>,
+    mut commands: Commands,
+) {
+    commands.remove_resource::<HintSearch>();
+    if pending.0.is_some() {
+        hints.current = None;
+        hints.message = None;
+        return;
+    }
+    let remaining = current_level
+        .0
+        .as_ref()
+        .and_then(|handle| levels.get(handle))
+        .map(|level| level.hints)
+        .unwrap_or(DEFAULT_HINTS);
+    *hints = Hints {
+        remaining,
+        ..default()
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+fn give_hint(
+    mut events: EventReader<RequestHint>,
+    mut hints: ResMut<Hints>,
+    search: Option<Res<HintSearch>>,
+    state: LevelState,
+    targets: Query<&Target>,
+    plants: Res<PlantDefinitions>,
+    rules: Res<PlayerRules>,
+    strings: Res<Strings>,
+    mut commands: Commands,
+) {
+    if events.iter().last().is_none()
+        || hints.remaining == 0
+        || hints.current.is_some()
+        || search.is_some()
+    {
+        return;
+    }
+    let Some(snapshot) = state.snapshot() else { return; };
+
+    let puzzle = Puzzle {
+        targets: targets.iter().cloned().collect(),
+        plants: plants.clone(),
+        rules: rules.clone(),
+    };
+    commands.insert_resource(HintSearch(Search::new(&snapshot, puzzle)));
+    hints.message = Some(strings.get("hint.searching"));
+}
+
+fn finish_hint(
+    search: Option<ResMut<HintSearch>>,
+    mut hints: ResMut<Hints>,
+    mut stats: ResMut<LevelStats>,
+    strings: Res<Strings>,
+    mut commands: Commands,
+) {
+    let Some(mut search) = search else { return; };
+    let action = match search.0.advance(STATES_PER_FRAME) {
+        SearchResult::Searching => return,
+        SearchResult::Found(action) => action,
+        SearchResult::NotFound => {
+            commands.remove_resource::<HintSearch>();
+            hints.message = Some(strings.get("hint.none"));
+            return;
+        }
+    };
+    commands.remove_resource::<HintSearch>();
+
+    hints.message = Some(match &action {
+        SolverAction::Move(direction, _) => strings.format(
+            "hint.move",
+            &[("direction", &strings.get(direction.label_key()))],
+        ),
+        SolverAction::UsePower(power, _) => {
+            strings.format("hint.use", &[("power", &strings.get(power.label_key()))])
+        }
+        SolverAction::Wait => strings.get("hint.wait"),
+    });
+    hints.current = Some(action);
+    hints.remaining -= 1;
+    stats.hints += 1;
+}
+
+/// A hint is only shown until the player does something, or the generation it was waiting for passes.
+/// Acting while a hint is still being worked out abandons the search, since it started from
+/// where the player was.
+fn clear_hint(
+    mut hints: ResMut<Hints>,
+    players: Query<&ActionState<Action>, With<Player>>,
+    generation: Res<Generation>,
+    mut commands: Commands,
+) {
+    if hints.current.is_none() && hints.message.is_none() {
+        return;
+    }
+    let acted = players
+        .get_single()
+        .map(|actions| !actions.get_just_pressed().is_empty())
+        .unwrap_or_default();
+    let waited = generation.is_changed() && hints.current == Some(SolverAction::Wait);
+    if acted || waited {
+        hints.current = None;
+        hints.message = None;
+    }
+    if acted {
+        commands.remove_resource::<HintSearch>();
+    }
+}
+
+fn draw_hint(
+    mut painter: ShapePainter,
+    hints: Res<Hints>,
+    time: Res<Time>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+) {
+    let tile = match hints.current.as_ref() {
+        Some(SolverAction::Move(_, tile)) | Some(SolverAction::UsePower(_, tile)) => *tile,
+        _ => return,
+    };
+    let Tile(x, y) = tile;
+    let pulse = if settings.reduced_motion {
+        1.
+    } else {
+        1. + 0.05 * (time.elapsed_seconds() * 4.).sin()
+    };
+
+    painter.set_translation(Vec3::new(x as f32, y as f32, 0.) * TILE_WORLD_SIZE + Vec3::Z * 5.);
+    painter.color = palette.pale;
+    painter.hollow = true;
+    painter.thickness = 4.;
+    painter.circle(TILE_WORLD_SIZE * 0.45 * pulse);
+    painter.hollow = false;
+}
@@ -0,0 +1,254 @@
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    control::{move_player, Action, AvailablePowers, Player, Power, Seed},
+    generate_tiles::LevelLoaded,
+    hazards::Health,
+    movement::{Abilities, Ability},
+    states::AppState,
+    target::{Target, UsedTarget},
+    tile::{Ground, Plant, Tile},
+    update_tiles::Generation,
+};
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UndoHistory>()
+            .init_resource::<PendingRestore>()
+            .add_system(reset_history.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(
+                record_history
+                    .in_set(OnUpdate(AppState::InGame))
+                    .before(move_player),
+            )
+            .add_system(
+                restore_snapshot
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            );
+    }
+}
+
+/// Everything needed to put a freshly generated level back into a previous state.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelSnapshot {
+    pub tiles: Vec<(Tile, Ground, Plant)>,
+    pub player: Tile,
+    pub powers: Vec<(Power, usize)>,
+    pub seed: Seed,
+    pub abilities: Vec<Ability>,
+    pub used_targets: Vec<Tile>,
+    pub generation: usize,
+    /// The player's health, or `None` for snapshots saved before health was recorded.
+    #[serde(default)]
+    pub health: Option<usize>,
+}
+
+/// Read access to all of the live level state that makes up a [`LevelSnapshot`].
+#[derive(SystemParam)]
+pub struct LevelState<'w, 's> {
+    tiles: Query<'w, 's, (&'static Tile, &'static Ground, &'static Plant)>,
+    players: Query<'w, 's, (&'static Player, Option<&'static Health>)>,
+    used_targets: Query<'w, 's, &'static Target, With<UsedTarget>>,
+    powers: Res<'w, AvailablePowers>,
+    seed: Res<'w, Seed>,
+    abilities: Res<'w, Abilities>,
+    generation: Res<'w, Generation>,
+}
+
+impl<'w, 's> LevelState<'w, 's> {
+    pub fn snapshot(&self) -> Option<LevelSnapshot> {
+        let (player, health) = self.players.get_single().ok()?;
+
+        let mut tiles = self
+            .tiles
+            .iter()
+            .map(|(t, g, p)| (*t, *g, p.clone()))
+            .collect::<Vec<_>>();
+        tiles.sort_by_key(|(Tile(x, y), _, _)| (*x, *y));
+
+        let mut powers = self
+            .powers
+            .0
+            .iter()
+            .map(|(p, v)| (p.clone(), *v))
+            .collect::<Vec<_>>();
+        powers.sort();
+
+        let mut abilities = self.abilities.0.iter().copied().collect::<Vec<_>>();
+        abilities.sort();
+
+        let mut used_targets = self.used_targets.iter().map(|t| t.0).collect::<Vec<_>>();
+        used_targets.sort_by_key(|Tile(x, y)| (*x, *y));
+
+        Some(LevelSnapshot {
+            tiles,
+            player: Tile(player.0, player.1),
+            powers,
+            seed: self.seed.clone(),
+            abilities,
+            used_targets,
+            generation: self.generation.0,
+            health: health.map(|h| h.0),
+        })
+    }
+}
+
+/// Snapshots taken right before each player action, most recent last.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct UndoHistory(pub Vec<LevelSnapshot>);
+
+/// A snapshot to apply as soon as the current level has been generated.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct PendingRestore(pub Option<LevelSnapshot>);
+
+fn reset_history(mut history: ResMut<UndoHistory>, pending: Res<PendingRestore>) {
+    if pending.0.is_none() {
+        history.0.clear();
+    }
+}
+
+pub fn record_history(
+    players: Query<&ActionState<Action>, With<Player>>,
+    state: LevelState,
+    mut history: ResMut<UndoHistory>,
+) {
+    let Ok(actions) = players.get_single() else { return; };
+    if actions.get_just_pressed().is_empty() {
+        return;
+    }
+
+    let Some(snapshot) = state.snapshot() else { return; };
+
+    if history.0.last() != Some(&snapshot) {
+        history.0.push(snapshot);
+    }
+}
+
+fn restore_snapshot(
+    mut pending: ResMut<PendingRestore>,
+    mut tiles: Query<(&Tile, &mut Ground, &mut Plant)>,
+    mut players: Query<(Entity, &mut Player)>,
+    targets: Query<(Entity, &Target), Without<UsedTarget>>,
+    mut commands: Commands,
+) {
+    let Some(snapshot) = pending.0.take() else { return; };
+
+    let saved = snapshot
+        .tiles
+        .iter()
+        .map(|(t, g, p)| (*t, (g, p)))
+        .collect::<HashMap<_, _>>();
+
+    for (tile, mut ground, mut plant) in tiles.iter_mut() {
+        if let Some((g, p)) = saved.get(tile) {
+            *ground = **g;
+            *plant = (*p).clone();
+        }
+    }
+
+    for (e, mut player) in players.iter_mut() {
+        player.0 = snapshot.player.0;
+        player.1 = snapshot.player.1;
+        if let Some(health) = snapshot.health {
+            commands.entity(e).insert(Health(health));
+        }
+    }
+
+    for (e, target) in targets.iter() {
+        if snapshot.used_targets.contains(&target.0) {
+            commands.entity(e).insert(UsedTarget).despawn_descendants();
+        }
+    }
+
+    commands.insert_resource(AvailablePowers(snapshot.powers.into_iter().collect()));
+    commands.insert_resource(snapshot.seed);
+    commands.insert_resource(Abilities(snapshot.abilities.into_iter().collect()));
+    commands.insert_resource(Generation(snapshot.generation));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{schedule::ExecutorKind, system::SystemState};
+
+    use super::*;
+    use crate::target::Reward;
+
+    /// A freshly generated level: a patch of soil with the player at one end and a shrine at the other.
+    fn level() -> World {
+        let mut world = World::new();
+        world.init_resource::<AvailablePowers>();
+        world.init_resource::<Seed>();
+        world.init_resource::<Abilities>();
+        world.init_resource::<Generation>();
+        world.init_resource::<PendingRestore>();
+        for x in 0..3 {
+            world.spawn((Tile(x, 0), Ground::Soil(false), Plant::Empty));
+        }
+        world.spawn((Player(0, 0), Health(3)));
+        world.spawn(Target(Tile(2, 0), "moss".to_string(), Reward::Seed));
+        world
+    }
+
+    fn snapshot(world: &mut World) -> LevelSnapshot {
+        let mut state = SystemState::<LevelState>::new(world);
+        state.get(world).snapshot().expect("no player")
+    }
+
+    #[test]
+    fn restoring_a_snapshot_puts_the_level_back() {
+        // Play the level a little.
+        let mut played = level();
+        for (tile, mut ground, mut plant) in played
+            .query::<(&Tile, &mut Ground, &mut Plant)>()
+            .iter_mut(&mut played)
+        {
+            if tile.0 > 0 {
+                *ground = Ground::Soil(true);
+                *plant = Plant::Plant("moss".to_string());
+            }
+        }
+        for (mut player, mut health) in played
+            .query::<(&mut Player, &mut Health)>()
+            .iter_mut(&mut played)
+        {
+            *player = Player(2, 0);
+            health.0 = 2;
+        }
+        let target = played
+            .query_filtered::<Entity, With<Target>>()
+            .single(&played);
+        played.entity_mut(target).insert(UsedTarget);
+        played.insert_resource(AvailablePowers([(Power::Plant, 2)].into_iter().collect()));
+        played.insert_resource(Seed(Some(("moss".to_string(), "moss.png".to_string()))));
+        played.insert_resource(Abilities([Ability::Swim].into_iter().collect()));
+        played.insert_resource(Generation(7));
+        let saved = snapshot(&mut played);
+
+        // Then reload it and restore what was saved.
+        let mut restored = level();
+        assert_ne!(snapshot(&mut restored), saved);
+        restored.insert_resource(PendingRestore(Some(saved.clone())));
+        let mut schedule = Schedule::new();
+        schedule
+            .set_executor_kind(ExecutorKind::SingleThreaded)
+            .add_system(restore_snapshot);
+        schedule.run(&mut restored);
+
+        assert_eq!(snapshot(&mut restored), saved);
+        assert!(restored.resource::<PendingRestore>().0.is_none());
+    }
+
+    #[test]
+    fn snapshots_survive_being_saved() {
+        let mut world = level();
+        let saved = snapshot(&mut world);
+        let text = serde_json::to_string(&saved).expect("couldn't save");
+        let loaded: LevelSnapshot = serde_json::from_str(&text).expect("couldn't load");
+        assert_eq!(loaded, saved);
+    }
+}
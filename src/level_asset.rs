@@ -1,13 +1,18 @@
-use std::{fmt, marker::PhantomData, str::FromStr};
+use std::{collections::BTreeMap, fmt, marker::PhantomData, str::FromStr};
 
 use bevy::{prelude::*, reflect::TypeUuid, utils::HashMap};
 use bevy_common_assets::{json::JsonAssetPlugin, yaml::YamlAssetPlugin};
 use serde::{
     de::{self, MapAccess, Visitor},
-    Deserialize, Deserializer, Serialize,
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 
-use crate::tile::{GameEntity, Ground, Plant, Tile};
+use crate::{
+    hazards::PlayerRules,
+    hint::DEFAULT_HINTS,
+    score::Par,
+    tile::{GameEntity, Ground, Plant, Tile},
+};
 
 pub struct LevelAssetPlugin;
 
@@ -23,21 +28,145 @@ impl Plugin for LevelAssetPlugin {
 
 #[derive(Clone, Serialize, Deserialize, TypeUuid)]
 #[uuid = "afc86691-e9b3-45d0-9b2d-109427c3bc18"]
-pub struct LevelList(pub Vec<String>);
+#[serde(from = "LevelListFormat")]
+pub struct LevelList {
+    pub chapters: Vec<Chapter>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    pub levels: Vec<String>,
+    /// The title in other languages, by language code.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub titles: BTreeMap<String, String>,
+}
+
+impl Chapter {
+    pub fn title_in(&self, language: &str) -> &str {
+        self.titles.get(language).unwrap_or(&self.title)
+    }
+}
+
+/// Level lists can either be a plain list of level files, or a list of chapters.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LevelListFormat {
+    Levels(Vec<String>),
+    Chapters { chapters: Vec<Chapter> },
+}
+
+impl From<LevelListFormat> for LevelList {
+    fn from(value: LevelListFormat) -> Self {
+        match value {
+            LevelListFormat::Levels(levels) => Self {
+                chapters: vec![Chapter {
+                    title: String::new(),
+                    levels,
+                    titles: BTreeMap::new(),
+                }],
+            },
+            LevelListFormat::Chapters { chapters } => Self { chapters },
+        }
+    }
+}
+
+impl LevelList {
+    /// Every level across all chapters, in play order.
+    pub fn levels(&self) -> Vec<String> {
+        self.chapters
+            .iter()
+            .flat_map(|chapter| chapter.levels.iter().cloned())
+            .collect()
+    }
+
+    pub fn next_after(&self, level: &str) -> Option<String> {
+        let levels = self.levels();
+        let index = levels.iter().position(|l| l == level)?;
+        levels.get(index + 1).cloned()
+    }
+}
 
 #[derive(Clone, Serialize, Deserialize, TypeUuid, Default)]
 #[uuid = "8301b47f-95b1-43b0-b4c3-32e45faa0f2f"]
 pub struct LevelAsset {
-    #[serde(deserialize_with = "strings_or_struct")]
-    pub tiles: LevelTiles,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub player_rules: PlayerRules,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub par: Par,
+    #[serde(default = "default_hints", skip_serializing_if = "is_default_hints")]
+    pub hints: usize,
+    /// How fast the simulation runs when the level starts, instead of the normal speed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    /// The track to play instead of the default music, relative to the assets folder.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub music: Option<String>,
+    /// The name and description in other languages, by language code.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, LevelText>,
+    #[serde(
+        deserialize_with = "strings_or_struct",
+        serialize_with = "to_string_serializer"
+    )]
+    pub tiles: LevelTiles,
+}
+
+/// A level's text in one language. Anything left out is shown in the level's own language.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelText {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 }
 
+impl LevelAsset {
+    pub fn name_in(&self, language: &str) -> &str {
+        self.translations
+            .get(language)
+            .and_then(|text| text.name.as_deref())
+            .unwrap_or(&self.name)
+    }
+
+    pub fn description_in(&self, language: &str) -> Option<&str> {
+        self.translations
+            .get(language)
+            .and_then(|text| text.description.as_deref())
+            .or(self.description.as_deref())
+    }
+}
+
+fn default_hints() -> usize {
+    DEFAULT_HINTS
+}
+
+fn is_default_hints(hints: &usize) -> bool {
+    *hints == DEFAULT_HINTS
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
 #[derive(Resource, Clone, Debug, Default)]
 pub struct CurrentLevel(pub Option<Handle<LevelAsset>>);
 
-#[derive(Clone, Default, Serialize, Deserialize)]
+/// The name a level is listed under in `levels.lvl.list.json`.
+pub fn level_file_name(server: &AssetServer, handle: &Handle<LevelAsset>) -> Option<String> {
+    server
+        .get_handle_path(handle)?
+        .path()
+        .file_name()?
+        .to_str()
+        .map(|name| name.to_string())
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct LevelTiles(pub HashMap<Tile, (Ground, Plant, Vec<GameEntity>)>);
 
 impl FromStr for LevelTiles {
@@ -49,11 +178,12 @@ impl FromStr for LevelTiles {
 
         let mut tiles = vec![];
         for (y, line) in s.lines().enumerate() {
-            max_y = max_y.max(y);
             let line = line.trim();
             if line.is_empty() {
                 continue;
             }
+            // Blank lines after the last row don't move the level.
+            max_y = max_y.max(y);
             for (x, tile) in line.split_whitespace().enumerate() {
                 max_x = max_x.max(x);
                 let mut split = tile.split(':');
@@ -65,19 +195,7 @@ impl FromStr for LevelTiles {
                     .unwrap_or_default();
 
                 let ground = ground
-                    .map(|g| Ground::from_str(g).unwrap_or_default())
-                    .map(|g| {
-                        if matches!(plant, Plant::Plant(_)) {
-                            match g {
-                                Ground::Soil(_) => Ground::Soil(true),
-                                Ground::Sand(_) => Ground::Sand(true),
-                                Ground::Rock(_) => Ground::Rock(true),
-                                _ => g,
-                            }
-                        } else {
-                            g
-                        }
-                    })
+                    .map(|g| Ground::from_str(g).unwrap_or_default().under(&plant))
                     .unwrap_or_default();
 
                 let content = (
@@ -102,6 +220,71 @@ impl FromStr for LevelTiles {
     }
 }
 
+/// Writes a tile as the `ground:plant:entity` token used in level files, dropping empty trailing parts.
+pub fn tile_token(ground: &Ground, plant: &Plant, entities: &[GameEntity]) -> String {
+    std::iter::once(ground.to_string())
+        .chain(std::iter::once(plant.to_string()))
+        .chain(entities.iter().map(|e| e.to_string()))
+        .collect::<Vec<_>>()
+        .join(":")
+        .trim_end_matches(':')
+        .to_string()
+}
+
+/// The inverse of `from_str`, laid out so that parsing the grid centres every tile on the
+/// same coordinates again. That holds for any tiles `from_str` produced - other layouts are
+/// written as closely as the grid allows, with gaps inside a row filled with empty ground.
+impl ToString for LevelTiles {
+    fn to_string(&self) -> String {
+        let tiles = &self.0;
+        if tiles.is_empty() {
+            return String::new();
+        }
+        let min_x = tiles.keys().map(|t| t.0 as i32).min().unwrap_or_default();
+        let min_y = tiles.keys().map(|t| t.1 as i32).min().unwrap_or_default();
+        let max_y = tiles.keys().map(|t| t.1 as i32).max().unwrap_or_default();
+
+        // Every row starts in the first column, so the leftmost tile sets the horizontal offset.
+        // Vertically, `from_str` centres on half the index of the last line with tiles - blank
+        // lines above the top row are kept, so pad there until the bottom row lands on that line.
+        let top = if -min_y - 1 >= max_y.max(0) {
+            -min_y - 1
+        } else {
+            (-min_y).max(max_y)
+        };
+
+        (min_y..=top)
+            .rev()
+            .map(|y| {
+                let row_end = tiles
+                    .keys()
+                    .filter(|t| t.1 as i32 == y)
+                    .map(|t| t.0 as i32)
+                    .max();
+                let Some(row_end) = row_end else { return String::new(); };
+                (min_x..=row_end)
+                    .map(|x| {
+                        tiles
+                            .get(&Tile(x as i8, y as i8))
+                            .map(|(ground, plant, entities)| tile_token(ground, plant, entities))
+                            .unwrap_or_else(|| Ground::Empty.to_string())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn to_string_serializer<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ToString,
+    S: Serializer,
+{
+    serializer.serialize_str(&value.to_string())
+}
+
 fn strings_or_struct<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     T: Deserialize<'de> + FromStr<Err = anyhow::Error>,
@@ -166,3 +349,50 @@ fn reload_current_level(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const GROUNDS: &[&str] = &["-", "w", "g", "gf", "s", "sf", "r", "rf"];
+    const PLANTS: &[&str] = &["", ".grass", ".flower", "f", "f1"];
+    const ENTITIES: &[&str] = &["", "p", "t.grass", "f.flower", "w.grass", "c.flower"];
+
+    fn tile() -> impl Strategy<Value = String> {
+        (
+            prop::sample::select(GROUNDS),
+            prop::sample::select(PLANTS),
+            prop::sample::select(ENTITIES),
+        )
+            .prop_map(|(ground, plant, entity)| format!("{ground}:{plant}:{entity}"))
+    }
+
+    /// A level grid with rows of different lengths, and blank lines anywhere - including before
+    /// the first row and after the last.
+    fn grid() -> impl Strategy<Value = String> {
+        let line = prop_oneof![
+            1 => Just(String::new()),
+            3 => prop::collection::vec(tile(), 1..10).prop_map(|tiles| tiles.join(" ")),
+        ];
+        prop::collection::vec(line, 1..10).prop_map(|lines| lines.join("\n"))
+    }
+
+    proptest! {
+        #[test]
+        fn tiles_survive_a_round_trip(text in grid()) {
+            let tiles = LevelTiles::from_str(&text).unwrap();
+            let written = tiles.to_string();
+            let reparsed = LevelTiles::from_str(&written).unwrap();
+            prop_assert_eq!(reparsed, tiles, "grid:\n{}\nwritten as:\n{}", text, written);
+        }
+    }
+
+    #[test]
+    fn trailing_blank_lines_are_ignored() {
+        let tiles = LevelTiles::from_str("g\n\n\n").unwrap();
+        assert_eq!(tiles, LevelTiles::from_str("g").unwrap());
+        assert!(tiles.0.contains_key(&Tile(0, 0)));
+    }
+}
@@ -1,4 +1,14 @@
-use crate::{assets::GameAssets, states::AppState};
+use crate::{
+    assets::GameAssets,
+    level_asset::LevelList,
+    level_list::PlayLevel,
+    locale::Strings,
+    progress::{format_time, LastResult},
+    replay::WatchReplay,
+    score::star_label,
+    states::AppState,
+    tile::PlantDefinitions,
+};
 use belly::{core::ess::Styles, prelude::*};
 use bevy::prelude::*;
 
@@ -14,22 +24,107 @@ impl Plugin for LevelCompleteScreenPlugin {
 #[derive(Component)]
 struct MenuItem;
 
-fn setup_menu(mut commands: Commands, assets: Res<GameAssets>, mut styles: ResMut<Styles>) {
+fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    result: Res<LastResult>,
+    level_list: Res<Assets<LevelList>>,
+    plants: Res<PlantDefinitions>,
+    strings: Res<Strings>,
+) {
     styles.insert(assets.ui_style.clone());
     let ui = commands.spawn(MenuItem).id();
 
+    let next_level = level_list
+        .get(&assets.levels)
+        .and_then(|list| list.next_after(&result.level))
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let score = &result.score;
+    let stars = star_label(score.stars, &strings);
+    let summary = strings.format(
+        "complete.summary",
+        &[
+            ("moves", &score.moves),
+            ("powers", &score.powers),
+            ("generations", &score.generations),
+            ("time", &format_time(result.time)),
+        ],
+    );
+    let bonus = (score.bonus_total > 0)
+        .then(|| {
+            strings.format(
+                "complete.bonus",
+                &[
+                    ("completed", &score.bonus_completed),
+                    ("total", &score.bonus_total),
+                ],
+            )
+        })
+        .into_iter()
+        .chain(result.bonus.iter().map(|(objective, complete)| {
+            let key = if *complete {
+                "complete.bonus_met"
+            } else {
+                "complete.bonus_missed"
+            };
+            strings.format(
+                key,
+                &[("objective", &objective.describe(&plants, &strings))],
+            )
+        }))
+        .collect::<Vec<_>>();
+    let hints = (score.hints > 0)
+        .then(|| strings.format("complete.hints", &[("hints", &score.hints)]))
+        .into_iter()
+        .collect::<Vec<_>>();
+    let best = if result.new_best {
+        strings.get("complete.new_best")
+    } else {
+        String::new()
+    };
+    let title = strings.get("complete.title");
+    let next_label = strings.get("complete.next");
+    let replay = strings.get("complete.replay");
+    let another = strings.get("complete.another");
+    let menu = strings.get("common.menu");
+
+    let root = strings.root_class("root");
     commands.add(eml! {
-        <body {ui} c:root>
+        <body {ui} class={root}>
             <div c:panel>
-                <div c:header>"Level Complete"</div>
-                <button c:menu_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::LevelList)))>
+                <div c:header>{title}</div>
+                <div c:stars>{stars}</div>
+                <div c:reason>{summary}</div>
+                <for bonus in=bonus>
+                    <div c:reason>{bonus}</div>
+                </for>
+                <for hints in=hints>
+                    <div c:reason>{hints}</div>
+                </for>
+                <div c:reason>{best}</div>
+                <for next in=next_level>
+                    <button c:menu_button on:press=move |ctx| ctx.send_event(PlayLevel(next.clone()))>
+                        <span c:content>
+                        {next_label.clone()}
+                        </span>
+                    </button>
+                </for>
+                <button c:menu_button c:small_menu_button c:secondary on:press=|ctx| ctx.send_event(WatchReplay)>
+                    <span c:content>
+                    {replay}
+                    </span>
+                </button>
+                <button c:menu_button c:small_menu_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::LevelList)))>
                     <span c:content>
-                    "Play Another Level"
+                    {another}
                     </span>
                 </button>
                 <button c:menu_button c:small_menu_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Menu)))>
                     <span c:content>
-                    "Menu"
+                    {menu}
                     </span>
                 </button>
             </div>
@@ -0,0 +1,103 @@
+use crate::{
+    assets::GameAssets,
+    failure::LevelFailure,
+    history::{PendingRestore, UndoHistory},
+    level_asset::CurrentLevel,
+    locale::Strings,
+    states::AppState,
+    tile::PlantDefinitions,
+};
+use belly::{core::ess::Styles, prelude::*};
+use bevy::prelude::*;
+
+pub struct LevelFailedScreenPlugin;
+
+impl Plugin for LevelFailedScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RecoverLevel>()
+            .add_system(setup_menu.in_schedule(OnEnter(AppState::LevelFailed)))
+            .add_system(recover_level.in_set(OnUpdate(AppState::LevelFailed)))
+            .add_system(clear_menu.in_schedule(OnExit(AppState::LevelFailed)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecoverLevel {
+    Undo,
+    Retry,
+}
+
+#[derive(Component)]
+struct MenuItem;
+
+fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    failure: Option<Res<LevelFailure>>,
+    history: Res<UndoHistory>,
+    plants: Res<PlantDefinitions>,
+    strings: Res<Strings>,
+) {
+    styles.insert(assets.ui_style.clone());
+    let ui = commands.spawn(MenuItem).id();
+
+    let reason = failure
+        .map(|failure| failure.reason.describe(&plants, &strings))
+        .unwrap_or_default();
+
+    let mut options = vec![];
+    if !history.0.is_empty() {
+        options.push((strings.get("failed.undo"), RecoverLevel::Undo));
+    }
+    options.push((strings.get("failed.retry"), RecoverLevel::Retry));
+    let title = strings.get("failed.title");
+    let menu = strings.get("common.menu");
+
+    let root = strings.root_class("root");
+    commands.add(eml! {
+        <body {ui} class={root}>
+            <div c:panel>
+                <div c:header>{title}</div>
+                <div c:reason>{reason}</div>
+                <for option in=options>
+                    <button c:menu_button c:small_menu_button on:press=move |ctx| ctx.send_event(option.1)>
+                        <span c:content>
+                        {option.0.clone()}
+                        </span>
+                    </button>
+                </for>
+                <button c:menu_button c:small_menu_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Menu)))>
+                    <span c:content>
+                    {menu}
+                    </span>
+                </button>
+            </div>
+        </body>
+    });
+}
+
+fn recover_level(
+    mut events: EventReader<RecoverLevel>,
+    failure: Option<Res<LevelFailure>>,
+    mut history: ResMut<UndoHistory>,
+    mut commands: Commands,
+) {
+    let Some(failure) = failure else { return; };
+    let Some(event) = events.iter().last() else { return; };
+
+    if *event == RecoverLevel::Undo {
+        let Some(snapshot) = history.0.pop() else { return; };
+        commands.insert_resource(PendingRestore(Some(snapshot)));
+    }
+
+    commands.insert_resource(CurrentLevel(Some(failure.level.clone())));
+    commands.insert_resource(NextState(Some(AppState::LoadingLevel)));
+}
+
+fn clear_menu(mut commands: Commands, query: Query<Entity, With<MenuItem>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<LevelFailure>();
+}
@@ -1,6 +1,9 @@
 use crate::{
     assets::GameAssets,
     level_asset::{CurrentLevel, LevelList},
+    locale::Strings,
+    progress::{format_time, LevelProgress, Progress},
+    score::star_label,
     states::AppState,
 };
 use belly::prelude::*;
@@ -10,7 +13,9 @@ pub struct LevelListPlugin;
 
 impl Plugin for LevelListPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(setup_menu.in_schedule(OnEnter(AppState::LevelList)))
+        app.add_event::<PlayLevel>()
+            .add_system(setup_menu.in_schedule(OnEnter(AppState::LevelList)))
+            .add_system(play_level)
             .add_system(clear_menu.in_schedule(OnExit(AppState::LevelList)));
     }
 }
@@ -18,32 +23,107 @@ impl Plugin for LevelListPlugin {
 #[derive(Component)]
 struct MenuItem;
 
-fn setup_menu(mut commands: Commands, assets: Res<GameAssets>, level_list: Res<Assets<LevelList>>) {
-    let Some(levels) = level_list.get(&assets.levels) else { return; };
+/// Loads a level from the level list by its file name and starts playing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayLevel(pub String);
 
-    let levels = levels.0.clone();
+fn play_level(
+    mut events: EventReader<PlayLevel>,
+    server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Some(PlayLevel(level)) = events.iter().last() else { return; };
+    let url = format!("levels/{level}");
+    commands.insert_resource(CurrentLevel(Some(server.load(url))));
+    commands.insert_resource(NextState(Some(AppState::LoadingLevel)));
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    level_list: Res<Assets<LevelList>>,
+    progress: Res<Progress>,
+    strings: Res<Strings>,
+) {
+    let Some(level_list) = level_list.get(&assets.levels) else { return; };
+
+    let all_levels = level_list.levels();
+    let chapters = level_list
+        .chapters
+        .iter()
+        .map(|chapter| {
+            let levels = chapter
+                .levels
+                .iter()
+                .map(|level| {
+                    let index = all_levels
+                        .iter()
+                        .position(|l| l == level)
+                        .unwrap_or_default();
+                    let unlocked = progress.is_unlocked(&all_levels, index);
+                    let badge = match progress.get(level) {
+                        _ if !unlocked => strings.get("level_list.locked"),
+                        Some(LevelProgress {
+                            completed: true,
+                            best_moves,
+                            best_time,
+                            best_stars,
+                        }) => strings.format(
+                            "level_list.best",
+                            &[
+                                ("stars", &star_label(*best_stars, &strings)),
+                                ("moves", &best_moves.unwrap_or_default()),
+                                ("time", &format_time(best_time.unwrap_or_default())),
+                            ],
+                        ),
+                        _ => String::new(),
+                    };
+                    let class = if unlocked {
+                        "menu_button small_menu_button"
+                    } else {
+                        "menu_button small_menu_button locked"
+                    };
+                    let label =
+                        strings.format("level_list.play", &[("level", &level_display(level))]);
+                    (level.clone(), badge, class.to_string(), unlocked, label)
+                })
+                .collect::<Vec<_>>();
+            (chapter.title_in(strings.language()).to_string(), levels)
+        })
+        .collect::<Vec<_>>();
+    let title = strings.get("menu.title");
+    let menu = strings.get("common.menu");
 
     let ui = commands.spawn(MenuItem).id();
 
+    let root = strings.root_class("root");
     commands.add(eml! {
-        <body {ui} c:root>
-            <div c:header>"Terra and Caelus"</div>
-                <for level in=levels>
-                    <button c:menu_button c:small_menu_button on:press=move |ctx| {
-                        let level = level.clone();
-                        let url = format!("levels/{level}");
-                        let handle = ctx.load(&url);
-                        ctx.commands().insert_resource(CurrentLevel(Some(handle)));
-                        ctx.commands().insert_resource(NextState(Some(AppState::LoadingLevel)));
-                    }>
-                        <span c:content>
-                        "Play "{level_display(&level)}
-                        </span>
-                    </button>
+        <body {ui} class={root}>
+            <div c:header>{title}</div>
+                <for chapter in=chapters>
+                    <div c:chapter>
+                        <div c:subheader>{chapter.0.clone()}</div>
+                        <for level in={chapter.1.clone()}>
+                            <div c:level_entry>
+                                <button class={level.2.clone()} on:press=move |ctx| {
+                                    let (level, _, _, unlocked, _) = level.clone();
+                                    if !unlocked {
+                                        return;
+                                    }
+                                    ctx.send_event(PlayLevel(level));
+                                }>
+                                    <span c:content>
+                                    {level.4.clone()}
+                                    </span>
+                                </button>
+                                <span c:level_badge>{level.1.clone()}</span>
+                            </div>
+                        </for>
+                    </div>
                 </for>
             <button c:menu_button c:small_menu_button c:secondary on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Menu)))>
                 <span c:content>
-                "Menu"
+                {menu}
                 </span>
             </button>
         </body>
@@ -13,7 +13,8 @@ pub struct LevelLoadingScreenPlugin;
 
 impl Plugin for LevelLoadingScreenPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(draw_loading.in_set(OnUpdate(AppState::LoadingLevel)));
+        app.add_system(enter_level.in_set(OnUpdate(AppState::LoadingLevel)))
+            .add_system(draw_loading.in_set(OnUpdate(AppState::LoadingLevel)));
     }
 }
 
@@ -23,9 +24,8 @@ struct LoadingEntity;
 const LOADING_ANIM_SPEED: f32 = 5.;
 const LOADING_ANIM_SIZE: f32 = 50.0;
 
-fn draw_loading(
-    mut painter: ShapePainter,
-    time: Res<Time>,
+/// Starts the level once it has loaded, or goes back to the menu if there isn't one to load.
+pub fn enter_level(
     current_level: Res<CurrentLevel>,
     level_assets: Res<Assets<LevelAsset>>,
     mut commands: Commands,
@@ -36,8 +36,10 @@ fn draw_loading(
     };
     if level_assets.get(current_level).is_some() {
         commands.insert_resource(NextState(Some(AppState::InGame)));
-        return;
     }
+}
+
+fn draw_loading(mut painter: ShapePainter, time: Res<Time>) {
     let offset = time.elapsed_seconds().mul(LOADING_ANIM_SPEED);
     let offset_x = offset.sin();
     let offset_y = offset.cos();
@@ -1,11 +1,25 @@
 use crate::{
+    almanac::ToggleAlmanac,
     assets::GameAssets,
-    control::{AvailablePowers, Seed},
+    bindings::Controls,
+    camera::CameraControl,
+    clock::{ClockControl, SimulationClock},
+    control::{Action, AvailablePowers, Power, Seed},
+    hazards::{Health, PlayerRules},
+    hint::{Hints, RequestHint},
     level_asset::{CurrentLevel, CurrentLevelHotReload, LevelAsset},
+    locale::Strings,
+    movement::Abilities,
+    pointer::{ArmPower, ArmedPower},
+    prediction::{Prediction, TogglePrediction},
+    settings::Settings,
+    solver::SolverAction,
     states::AppState,
+    suspend::SuspendLevel,
+    tile::PlantDefinitions,
 };
 use belly::{core::ess::Styles, prelude::*};
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 
 pub struct LevelUiPlugin;
 
@@ -23,6 +37,17 @@ impl Plugin for LevelUiPlugin {
 #[derive(Component)]
 struct MenuItem;
 
+/// The hint, overlay, clock, settings and language state shown alongside the level.
+#[derive(SystemParam)]
+struct LevelPanel<'w> {
+    hints: Res<'w, Hints>,
+    prediction: Res<'w, Prediction>,
+    clock: Res<'w, SimulationClock>,
+    settings: Res<'w, Settings>,
+    plants: Res<'w, PlantDefinitions>,
+    strings: Res<'w, Strings>,
+}
+
 #[allow(clippy::too_many_arguments)]
 fn setup_menu(
     mut commands: Commands,
@@ -33,11 +58,35 @@ fn setup_menu(
     query: Query<Entity, With<MenuItem>>,
     current_level: Res<CurrentLevel>,
     level_assets: Res<Assets<LevelAsset>>,
+    health: Query<Ref<Health>>,
+    rules: Res<PlayerRules>,
+    abilities: Res<Abilities>,
+    armed: Res<ArmedPower>,
+    controls: Res<Controls>,
+    panel: LevelPanel,
 ) {
     let Some(current_level) = current_level.0.as_ref() else { return; };
     let Some(level) = level_assets.get(current_level) else { return;};
+    let LevelPanel {
+        hints,
+        prediction,
+        clock,
+        settings,
+        plants,
+        strings,
+    } = panel;
 
-    if !powers.is_changed() {
+    if !powers.is_changed()
+        && !abilities.is_changed()
+        && !armed.is_changed()
+        && !controls.is_changed()
+        && !hints.is_changed()
+        && !prediction.is_changed()
+        && !clock.is_changed()
+        && !settings.is_changed()
+        && !strings.is_changed()
+        && !health.iter().any(|h| h.is_changed())
+    {
         return;
     }
 
@@ -52,46 +101,135 @@ fn setup_menu(
         .0
         .iter()
         .filter_map(|(p, v)| if *v > 0 { Some((p.clone(), *v)) } else { None })
+        .map(|(p, v)| {
+            let mut class = if armed.0.as_ref() == Some(&p) {
+                format!("{} armed", p.ui_class_name())
+            } else {
+                p.ui_class_name()
+            };
+            if matches!(&hints.current, Some(SolverAction::UsePower(hinted, _)) if *hinted == p) {
+                class.push_str(" hinted");
+            }
+            let key = controls.label(p.action());
+            // The plant card is labelled with the seed it will plant.
+            let label = match (&p, &seed.0) {
+                (Power::Plant, Some((id, _))) => plants.name(id),
+                _ => strings.get(p.label_key()),
+            };
+            (p, v, class, key, label)
+        })
         .collect::<Vec<_>>();
     let seed = seed.clone();
-    let level_header = level.name.clone();
+    let up = controls.label(Action::Up);
+    let down = controls.label(Action::Down);
+    let left = controls.label(Action::Left);
+    let right = controls.label(Action::Right);
+    let health = health
+        .iter()
+        .next()
+        .map(|h| strings.format("level.health", &[("health", &h.0), ("max", &rules.health)]))
+        .unwrap_or_default();
+    let mut abilities = abilities.0.iter().copied().collect::<Vec<_>>();
+    abilities.sort();
+    let abilities = abilities
+        .iter()
+        .map(|a| strings.get(a.label_key()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let hint_button = (hints.remaining > 0)
+        .then(|| strings.format("level.hint", &[("remaining", &hints.remaining)]))
+        .into_iter()
+        .collect::<Vec<_>>();
+    let preview = strings.get(if prediction.visible {
+        "level.hide_growth"
+    } else {
+        "level.show_growth"
+    });
+    let contrast = if settings.high_contrast {
+        " high_contrast"
+    } else {
+        ""
+    };
+    let description_class = format!("description{contrast}");
+    let cards_class = format!("cards{contrast}");
+    let clock_label = clock.label(&strings);
+    let pause_label = strings.get(if clock.paused {
+        "level.play"
+    } else {
+        "level.pause"
+    });
+    let hint_message = hints.message.clone().into_iter().collect::<Vec<_>>();
+    let level_header = level.name_in(strings.language()).to_string();
     let level_description = level
-        .description
-        .clone()
+        .description_in(strings.language())
         .unwrap_or_default()
         .lines()
         .map(|v| v.to_string())
         .collect::<Vec<_>>();
+    let restart = strings.get("level.restart");
+    let exit = strings.get("level.exit");
+    let almanac = strings.get("level.almanac");
+    let step = strings.get("level.step");
+    let view = strings.get("level.view");
+    let fit = strings.get("level.fit");
+    let follow = strings.get("level.follow");
+    let map = strings.get("level.map");
 
+    let root = strings.root_class("in_game");
     commands.add(eml! {
-        <body {ui} c:in_game>
-                <div c:description>
+        <body {ui} class={root}>
+                <div class={description_class}>
                     <div c:desc_header>
                         <div c:level_header>{level_header}</div>
                         <div>
-                        <button c:exit_button on:press=|ctx| ctx.send_event(CurrentLevelHotReload)><span c:content>"Restart"</span></button>
-                        <button c:exit_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Menu)))><span c:content>"Exit"</span></button>
+                        <button c:exit_button on:press=|ctx| ctx.send_event(CurrentLevelHotReload)><span c:content>{restart}</span></button>
+                        <button c:exit_button on:press=|ctx| ctx.send_event(SuspendLevel)><span c:content>{exit}</span></button>
+                        <button c:exit_button on:press=|ctx| ctx.send_event(TogglePrediction)><span c:content>{preview}</span></button>
+                        <button c:exit_button on:press=|ctx| ctx.send_event(ToggleAlmanac)><span c:content>{almanac}</span></button>
+                        <for label in=hint_button>
+                            <button c:exit_button on:press=|ctx| ctx.send_event(RequestHint)><span c:content>{label}</span></button>
+                        </for>
                         </div>
                     </div>
+                    <for message in=hint_message>
+                        <div c:hint>{message}</div>
+                    </for>
+                    <div c:clock>
+                        <span c:clock_label>{clock_label}</span>
+                        <button c:clock_button on:press=|ctx| ctx.send_event(ClockControl::Slower)><span c:content>"-"</span></button>
+                        <button c:clock_button on:press=|ctx| ctx.send_event(ClockControl::TogglePause)><span c:content>{pause_label}</span></button>
+                        <button c:clock_button on:press=|ctx| ctx.send_event(ClockControl::Step)><span c:content>{step}</span></button>
+                        <button c:clock_button on:press=|ctx| ctx.send_event(ClockControl::Faster)><span c:content>"+"</span></button>
+                    </div>
+                    <div c:clock>
+                        <span c:clock_label>{view}</span>
+                        <button c:clock_button on:press=|ctx| ctx.send_event(CameraControl::ZoomOut)><span c:content>"-"</span></button>
+                        <button c:clock_button on:press=|ctx| ctx.send_event(CameraControl::Fit)><span c:content>{fit}</span></button>
+                        <button c:clock_button on:press=|ctx| ctx.send_event(CameraControl::Follow)><span c:content>{follow}</span></button>
+                        <button c:clock_button on:press=|ctx| ctx.send_event(CameraControl::ZoomIn)><span c:content>"+"</span></button>
+                        <button c:clock_button on:press=|ctx| ctx.send_event(CameraControl::ToggleMinimap)><span c:content>{map}</span></button>
+                    </div>
+                    <div c:health>{health}</div>
+                    <div c:abilities>{abilities}</div>
                     <for dsc in=level_description>
                         <div c:level_description>{dsc}</div>
                     </for>
                 </div>
-                <div c:cards>
+                <div class={cards_class}>
                     <div c:card c:movement>
                         <img c:card-image src="card_move.png"></img>
-                        <span c:key_bind c:up>"W"</span>
-                        <span c:key_bind c:down>"S"</span>
-                        <span c:key_bind c:left>"A"</span>
-                        <span c:key_bind c:right>"D"</span>
+                        <span c:key_bind c:up>{up}</span>
+                        <span c:key_bind c:down>{down}</span>
+                        <span c:key_bind c:left>{left}</span>
+                        <span c:key_bind c:right>{right}</span>
                     </div>
                     <for value in=powers>
-                        <div class={value.0.ui_class_name()}>
-                            <img c:card-image src={value.0.ui_image(&seed).0}></img>
-                            <span c:label>{value.0.to_string()}</span>
+                        <button class={value.2.clone()} on:press=move |ctx| ctx.send_event(ArmPower(value.0.clone()))>
+                            <img c:card-image src={value.0.ui_image(&seed)}></img>
+                            <span c:label>{value.4.clone()}</span>
                             <span c:available>{value.1.to_string()}</span>
-                            <span c:key_bind>{value.0.key_binding()}</span>
-                        </div>
+                            <span c:key_bind>{value.3.clone()}</span>
+                        </button>
                     </for>
                 </div>
         </body>
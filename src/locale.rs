@@ -0,0 +1,146 @@
+//! Translated text for the UI, looked up by key from per-language string tables.
+//! Languages needing letters `font.ttf` doesn't have get their font from `ui.ess`, which
+//! matches the `lang_<code>` class on the root of every UI.
+
+use std::{collections::HashMap, fmt::Display};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{settings::Settings, tile::PlantDefinitions};
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Strings>()
+            .add_system(apply_language)
+            .add_system(sync_plant_language.after(apply_language));
+    }
+}
+
+/// The language everything falls back to when a string hasn't been translated.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Languages needing letters `font.ttf` doesn't have. The UI picks their font in `ui.ess`,
+/// text drawn in the world asks [`Strings::uses_fallback_font`].
+const FALLBACK_FONT_LANGUAGES: [&str; 1] = ["ru"];
+
+/// The string tables, built into the game so text is available before any assets load.
+const TABLES: [&str; 3] = [
+    include_str!("../assets/locales/en.json"),
+    include_str!("../assets/locales/es.json"),
+    include_str!("../assets/locales/ru.json"),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+struct StringTable {
+    code: String,
+    /// The language's name, written in that language.
+    name: String,
+    strings: HashMap<String, String>,
+}
+
+/// The string tables and the language currently shown.
+#[derive(Resource, Debug, Clone)]
+pub struct Strings {
+    tables: Vec<StringTable>,
+    current: usize,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        let tables = TABLES
+            .iter()
+            .map(|table| serde_json::from_str::<StringTable>(table).expect("invalid string table"))
+            .collect::<Vec<_>>();
+        let current = tables
+            .iter()
+            .position(|table| table.code == DEFAULT_LANGUAGE)
+            .unwrap_or_default();
+        Self { tables, current }
+    }
+}
+
+impl Strings {
+    /// The code of the language being shown, like "en".
+    pub fn language(&self) -> &str {
+        &self.tables[self.current].code
+    }
+
+    /// Whether the current language is drawn with `fonts/DejaVuSans.ttf` rather than `font.ttf`.
+    pub fn uses_fallback_font(&self) -> bool {
+        FALLBACK_FONT_LANGUAGES.contains(&self.language())
+    }
+
+    /// The codes of every language with a string table.
+    pub fn languages(&self) -> Vec<&str> {
+        self.tables
+            .iter()
+            .map(|table| table.code.as_str())
+            .collect()
+    }
+
+    /// A language's own name for itself.
+    pub fn language_name(&self, code: &str) -> String {
+        self.tables
+            .iter()
+            .find(|table| table.code == code)
+            .map(|table| table.name.clone())
+            .unwrap_or_else(|| code.to_string())
+    }
+
+    /// The text for a key in the current language, falling back to English and then the key itself.
+    pub fn get(&self, key: &str) -> String {
+        self.tables[self.current]
+            .strings
+            .get(key)
+            .or_else(|| {
+                self.tables
+                    .iter()
+                    .find(|table| table.code == DEFAULT_LANGUAGE)
+                    .and_then(|table| table.strings.get(key))
+            })
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// The text for a key with each `{name}` in it replaced by the matching argument.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        args.iter().fold(self.get(key), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+    }
+
+    /// The classes for the root of a UI, tagged with the language so `ui.ess` can pick its font.
+    pub fn root_class(&self, class: &str) -> String {
+        format!("{class} lang_{}", self.language())
+    }
+
+    /// Several names as a list to read out, like "Moss or Grass".
+    pub fn list_or(&self, names: impl IntoIterator<Item = String>) -> String {
+        names
+            .into_iter()
+            .collect::<Vec<_>>()
+            .join(&self.get("list.or"))
+    }
+}
+
+fn apply_language(settings: Res<Settings>, mut strings: ResMut<Strings>) {
+    if !settings.is_changed() || strings.language() == settings.language {
+        return;
+    }
+    let Some(index) = strings
+        .tables
+        .iter()
+        .position(|table| table.code == settings.language) else { return; };
+    strings.current = index;
+}
+
+/// Plant names and descriptions come from the plant definitions, so they follow the language separately.
+fn sync_plant_language(strings: Res<Strings>, plants: Option<ResMut<PlantDefinitions>>) {
+    let Some(mut plants) = plants else { return; };
+    if plants.language != strings.language() {
+        plants.language = strings.language().to_string();
+    }
+}
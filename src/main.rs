@@ -1,45 +1,100 @@
+mod almanac;
+mod animation;
 mod assets;
+mod bindings;
+mod camera;
+mod clock;
 mod colors;
 mod control;
+mod controls_screen;
 mod credits;
 mod display;
+mod editor;
+mod failure;
 mod generate_tiles;
+mod glyphs;
+mod hazards;
+mod headless;
+mod history;
+mod hint;
 mod level_asset;
 mod level_complete_screen;
+mod level_failed_screen;
 mod level_list;
 mod level_loading_screen;
 mod level_ui;
 mod loading_screen;
+mod locale;
 mod menu;
+mod movement;
+mod music;
+mod pointer;
+mod prediction;
+mod progress;
+mod replay;
+mod score;
+mod settings;
+mod sfx;
+mod solver;
 mod states;
+mod storage;
+mod suspend;
 mod target;
 mod tile;
+mod tile_info;
 mod update_tiles;
 
 use std::time::Duration;
 
+use almanac::AlmanacPlugin;
+use animation::AnimationPlugin;
 use assets::GameAssets;
 use belly::prelude::BellyPlugin;
-use bevy::{input::common_conditions::input_toggle_active, prelude::*};
+use bevy::prelude::*;
 use bevy_asset_loader::prelude::{LoadingState, LoadingStateAppExt};
 // use bevy_kira_audio::{prelude::*, Audio};
 
+#[cfg(feature = "debug")]
+use bevy::input::common_conditions::input_toggle_active;
+#[cfg(feature = "debug")]
 use bevy_inspector_egui::quick::ResourceInspectorPlugin;
 use bevy_vector_shapes::Shape2dPlugin;
+use bindings::BindingsPlugin;
+use camera::CameraPlugin;
+use clock::ClockPlugin;
 use control::ControlPlugin;
+use controls_screen::ControlsScreenPlugin;
 use credits::CreditsPlugin;
 use display::TileDisplayPlugin;
+use editor::EditorPlugin;
+use failure::FailurePlugin;
 use generate_tiles::TileGeneratorPlugin;
+use glyphs::GlyphPlugin;
+use hazards::HazardPlugin;
+use history::HistoryPlugin;
+use hint::HintPlugin;
 use level_asset::LevelAssetPlugin;
 use level_complete_screen::LevelCompleteScreenPlugin;
+use level_failed_screen::LevelFailedScreenPlugin;
 use level_list::LevelListPlugin;
 use level_loading_screen::LevelLoadingScreenPlugin;
 use level_ui::LevelUiPlugin;
 use loading_screen::LoadingScreenPlugin;
+use locale::LocalePlugin;
 use menu::MenuPlugin;
+use movement::MovementPlugin;
+use music::MusicPlugin;
+use pointer::PointerPlugin;
+use prediction::PredictionPlugin;
+use progress::ProgressPlugin;
+use replay::ReplayPlugin;
+use settings::SettingsPlugin;
+use sfx::SfxPlugin;
 use states::AppState;
+use suspend::SuspendPlugin;
 use target::TargetPlugin;
 use tile::{PlantDefinitions, TilePlugin};
+use tile_info::TileInfoPlugin;
 use update_tiles::UpdateTilesPlugin;
 
 fn main() {
@@ -47,7 +102,12 @@ fn main() {
     #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
 
-    App::new()
+    if headless::requested() {
+        std::process::exit(headless::run_from_args());
+    }
+
+    let mut app = App::new();
+    app
         // Resource Setup
         .add_state::<AppState>()
         .insert_resource(ClearColor(colors::BACKGROUND))
@@ -69,10 +129,6 @@ fn main() {
         // External Plugins
         .add_plugin(Shape2dPlugin::default())
         .add_plugin(BellyPlugin)
-        .add_plugin(
-            ResourceInspectorPlugin::<PlantDefinitions>::default()
-                .run_if(input_toggle_active(false, KeyCode::Escape)),
-        )
         // .add_plugin(AudioPlugin)
         .add_loading_state(
             LoadingState::new(AppState::LoadingAssets).continue_to_state(AppState::Menu),
@@ -86,27 +142,51 @@ fn main() {
         .add_plugin(LevelListPlugin)
         .add_plugin(LevelLoadingScreenPlugin)
         .add_plugin(LevelCompleteScreenPlugin)
+        .add_plugin(LevelFailedScreenPlugin)
         .add_plugin(LevelUiPlugin)
         .add_plugin(CreditsPlugin)
+        .add_plugin(ControlsScreenPlugin)
+        .add_plugin(EditorPlugin)
         .add_plugin(TilePlugin)
         .add_plugin(TileGeneratorPlugin)
         .add_plugin(TileDisplayPlugin)
+        .add_plugin(GlyphPlugin)
+        .add_plugin(AnimationPlugin)
         .add_plugin(UpdateTilesPlugin)
+        .add_plugin(ClockPlugin)
+        .add_plugin(BindingsPlugin)
         .add_plugin(ControlPlugin)
+        .add_plugin(MovementPlugin)
+        .add_plugin(PointerPlugin)
+        .add_plugin(CameraPlugin)
         .add_plugin(TargetPlugin)
+        .add_plugin(HazardPlugin)
+        .add_plugin(HistoryPlugin)
+        .add_plugin(FailurePlugin)
+        .add_plugin(ProgressPlugin)
+        .add_plugin(SuspendPlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(HintPlugin)
+        .add_plugin(PredictionPlugin)
+        .add_plugin(TileInfoPlugin)
+        .add_plugin(SfxPlugin)
+        .add_plugin(MusicPlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(LocalePlugin)
+        .add_plugin(AlmanacPlugin)
         // Systems
-        .add_startup_system(setup)
-        .add_system(start_audio.in_schedule(OnExit(AppState::LoadingAssets)))
-        .run();
+        .add_startup_system(setup);
+
+    // Only in debug builds, on a key the game doesn't use.
+    #[cfg(feature = "debug")]
+    app.add_plugin(
+        ResourceInspectorPlugin::<PlantDefinitions>::default()
+            .run_if(input_toggle_active(false, KeyCode::F12)),
+    );
+
+    app.run();
 }
 
 fn setup(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
-
-fn start_audio(audio: Res<Audio>, assets: Res<GameAssets>) {
-    audio.play_with_settings(
-        assets.music.clone(),
-        PlaybackSettings::LOOP.with_volume(0.8),
-    );
-}
@@ -1,4 +1,9 @@
-use crate::{assets::GameAssets, states::AppState};
+use crate::{
+    assets::GameAssets,
+    locale::Strings,
+    states::AppState,
+    suspend::{ResumeLevel, Suspended},
+};
 use belly::{core::ess::Styles, prelude::*};
 use bevy::prelude::*;
 
@@ -14,22 +19,79 @@ impl Plugin for MenuPlugin {
 #[derive(Component)]
 struct MenuItem;
 
-fn setup_menu(mut commands: Commands, assets: Res<GameAssets>, mut styles: ResMut<Styles>) {
+fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    suspended: Res<Suspended>,
+    strings: Res<Strings>,
+) {
     styles.insert(assets.ui_style.clone());
     let ui = commands.spawn(MenuItem).id();
 
+    let continue_level = suspended
+        .0
+        .as_ref()
+        .map(|suspended| {
+            strings.format(
+                "menu.continue",
+                &[(
+                    "level",
+                    &suspended.level.replace(".lvl.yaml", "").replace('_', " "),
+                )],
+            )
+        })
+        .into_iter()
+        .collect::<Vec<_>>();
+    let title = strings.get("menu.title");
+    let byline = strings.get("menu.byline");
+    let play = strings.get("menu.play");
+    let controls = strings.get("menu.controls");
+    let almanac = strings.get("menu.almanac");
+    let settings = strings.get("menu.settings");
+    let editor = strings.get("menu.editor");
+    let credits = strings.get("menu.credits");
+
+    let root = strings.root_class("root");
     commands.add(eml! {
-        <body {ui} c:root>
-            <div c:header>"Terra and Caelus"</div>
-            <div c:subheader>"A Game By Lee-Orr"</div>
+        <body {ui} class={root}>
+            <div c:header>{title}</div>
+            <div c:subheader>{byline}</div>
+            <for level in=continue_level>
+                <button c:menu_button on:press=|ctx| ctx.send_event(ResumeLevel)>
+                    <span c:content>
+                    {level}
+                    </span>
+                </button>
+            </for>
             <button c:menu_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::LevelList)))>
                 <span c:content>
-                "Play"
+                {play}
+                </span>
+            </button>
+            <button c:menu_button c:small_menu_button c:secondary on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Controls)))>
+                <span c:content>
+                {controls}
+                </span>
+            </button>
+            <button c:menu_button c:small_menu_button c:secondary on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Almanac)))>
+                <span c:content>
+                {almanac}
+                </span>
+            </button>
+            <button c:menu_button c:small_menu_button c:secondary on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Settings)))>
+                <span c:content>
+                {settings}
+                </span>
+            </button>
+            <button c:menu_button c:small_menu_button c:secondary on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Editor)))>
+                <span c:content>
+                {editor}
                 </span>
             </button>
             <button c:menu_button c:small_menu_button c:secondary on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Credits)))>
                 <span c:content>
-                "Credits"
+                {credits}
                 </span>
             </button>
         </body>
@@ -0,0 +1,279 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    generate_tiles::LevelLoaded,
+    hazards::PlayerRules,
+    states::AppState,
+    tile::{Ground, Plant, Tile},
+};
+
+pub struct MovementPlugin;
+
+impl Plugin for MovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Abilities>()
+            .add_event::<GainAbility>()
+            .add_system(gain_ability.in_set(OnUpdate(AppState::InGame)))
+            .add_system(reset_abilities.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(reset_abilities.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                reset_abilities
+                    .run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Ability {
+    /// Enter water tiles, with or without plants on them.
+    Swim,
+    /// Drift across a single gap of empty ground.
+    Float,
+    /// Enter rock tiles, with or without plants on them.
+    Climb,
+}
+
+impl Ability {
+    /// The key of the ability's name in the string tables.
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Ability::Swim => "ability.swim",
+            Ability::Float => "ability.float",
+            Ability::Climb => "ability.climb",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct GainAbility(pub Ability);
+
+#[derive(Resource, Default, Debug, Clone, PartialEq, Eq)]
+pub struct Abilities(pub HashSet<Ability>);
+
+pub const DIRECTIONS: [(i8, i8); 4] = [(0, 1), (0, -1), (-1, 0), (1, 0)];
+
+impl Abilities {
+    pub fn has(&self, ability: Ability) -> bool {
+        self.0.contains(&ability)
+    }
+
+    /// Whether the player may stand on a tile. `living_only` restricts plant
+    /// based movement to living plants, as happens when the player is stranded.
+    pub fn can_enter(&self, ground: &Ground, plant: &Plant, living_only: bool) -> bool {
+        match plant {
+            Plant::Plant(_) => true,
+            Plant::Fire(_) if !living_only => true,
+            _ => match ground {
+                Ground::Water => self.has(Ability::Swim),
+                Ground::Rock(_) => self.has(Ability::Climb),
+                _ => false,
+            },
+        }
+    }
+
+    /// Where a single step from `from` in `direction` would land, if it is allowed.
+    pub fn step(
+        &self,
+        from: Tile,
+        direction: (i8, i8),
+        tiles: &HashMap<Tile, (&Ground, &Plant)>,
+        rules: &PlayerRules,
+    ) -> Option<Tile> {
+        let living_only = rules.stranded_living_only
+            && matches!(tiles.get(&from), Some((_, Plant::Empty)) | None);
+
+        let target = Tile(from.0 + direction.0, from.1 + direction.1);
+        let (ground, plant) = tiles.get(&target)?;
+        if self.can_enter(ground, plant, living_only) {
+            return Some(target);
+        }
+
+        if self.has(Ability::Float) && **ground == Ground::Empty {
+            let beyond = Tile(target.0 + direction.0, target.1 + direction.1);
+            let (ground, plant) = tiles.get(&beyond)?;
+            if self.can_enter(ground, plant, living_only) {
+                return Some(beyond);
+            }
+        }
+
+        None
+    }
+
+    /// Every tile the player can reach from `from` with a single step.
+    pub fn neighbours(
+        &self,
+        from: Tile,
+        tiles: &HashMap<Tile, (&Ground, &Plant)>,
+        rules: &PlayerRules,
+    ) -> Vec<Tile> {
+        DIRECTIONS
+            .iter()
+            .filter_map(|direction| self.step(from, *direction, tiles, rules))
+            .collect()
+    }
+}
+
+fn reset_abilities(mut commands: Commands) {
+    commands.insert_resource(Abilities::default());
+}
+
+fn gain_ability(mut gain_ability: EventReader<GainAbility>, mut abilities: ResMut<Abilities>) {
+    for GainAbility(ability) in gain_ability.iter() {
+        abilities.0.insert(*ability);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abilities(list: &[Ability]) -> Abilities {
+        Abilities(list.iter().copied().collect())
+    }
+
+    fn board(tiles: &[(Tile, Ground, Plant)]) -> HashMap<Tile, (&Ground, &Plant)> {
+        tiles.iter().map(|(t, g, p)| (*t, (g, p))).collect()
+    }
+
+    /// A row starting with the player on grass, followed by the given tiles to the right.
+    fn row(ahead: &[(Ground, Plant)]) -> Vec<(Tile, Ground, Plant)> {
+        let mut tiles = vec![(Tile(0, 0), Ground::Soil(true), grass())];
+        for (x, (ground, plant)) in ahead.iter().enumerate() {
+            tiles.push((Tile(x as i8 + 1, 0), *ground, plant.clone()));
+        }
+        tiles
+    }
+
+    fn grass() -> Plant {
+        Plant::Plant("grass".to_string())
+    }
+
+    fn step_right(list: &[Ability], ahead: &[(Ground, Plant)]) -> Option<Tile> {
+        let tiles = row(ahead);
+        abilities(list).step(Tile(0, 0), (1, 0), &board(&tiles), &PlayerRules::default())
+    }
+
+    #[test]
+    fn can_enter_each_ground() {
+        let cases = [
+            // (abilities, ground, plant, can enter)
+            (vec![], Ground::Soil(false), Plant::Empty, false),
+            (vec![], Ground::Soil(false), grass(), true),
+            (vec![], Ground::Soil(false), Plant::Fire(1), true),
+            (vec![], Ground::Water, Plant::Empty, false),
+            (vec![], Ground::Water, grass(), true),
+            (vec![Ability::Swim], Ground::Water, Plant::Empty, true),
+            (vec![Ability::Climb], Ground::Water, Plant::Empty, false),
+            (vec![], Ground::Rock(false), Plant::Empty, false),
+            (
+                vec![Ability::Climb],
+                Ground::Rock(false),
+                Plant::Empty,
+                true,
+            ),
+            (vec![Ability::Climb], Ground::Rock(true), Plant::Empty, true),
+            (
+                vec![Ability::Swim],
+                Ground::Rock(false),
+                Plant::Empty,
+                false,
+            ),
+            (vec![Ability::Float], Ground::Empty, Plant::Empty, false),
+            (
+                vec![Ability::Swim, Ability::Climb],
+                Ground::Sand(false),
+                Plant::Empty,
+                false,
+            ),
+        ];
+        for (list, ground, plant, expected) in cases {
+            assert_eq!(
+                abilities(&list).can_enter(&ground, &plant, false),
+                expected,
+                "{list:?} entering {ground:?} with {plant:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn living_only_refuses_fire() {
+        let none = abilities(&[]);
+        assert!(!none.can_enter(&Ground::Soil(false), &Plant::Fire(1), true));
+        assert!(none.can_enter(&Ground::Soil(false), &grass(), true));
+    }
+
+    #[test]
+    fn steps_with_each_ability() {
+        let gap = (Ground::Empty, Plant::Empty);
+        let field = (Ground::Soil(true), grass());
+        let water = (Ground::Water, Plant::Empty);
+        let rock = (Ground::Rock(false), Plant::Empty);
+        let cases = [
+            // (abilities, tiles to the right, where the step lands)
+            (vec![], vec![field.clone()], Some(Tile(1, 0))),
+            (vec![], vec![water.clone()], None),
+            (vec![Ability::Swim], vec![water.clone()], Some(Tile(1, 0))),
+            (vec![], vec![rock.clone()], None),
+            (vec![Ability::Climb], vec![rock.clone()], Some(Tile(1, 0))),
+            (vec![], vec![gap.clone(), field.clone()], None),
+            (
+                vec![Ability::Float],
+                vec![gap.clone(), field.clone()],
+                Some(Tile(2, 0)),
+            ),
+            (
+                vec![Ability::Float],
+                vec![gap.clone(), gap.clone(), field.clone()],
+                None,
+            ),
+            (vec![Ability::Float], vec![gap.clone(), water.clone()], None),
+            (
+                vec![Ability::Float, Ability::Swim],
+                vec![gap.clone(), water.clone()],
+                Some(Tile(2, 0)),
+            ),
+            (vec![Ability::Float], vec![gap.clone()], None),
+            (
+                vec![Ability::Float],
+                vec![water.clone(), field.clone()],
+                None,
+            ),
+            (vec![Ability::Swim], vec![], None),
+        ];
+        for (list, ahead, expected) in cases {
+            assert_eq!(
+                step_right(&list, &ahead),
+                expected,
+                "{list:?} stepping onto {ahead:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn neighbours_are_every_open_step() {
+        let tiles = vec![
+            (Tile(0, 0), Ground::Soil(true), grass()),
+            (Tile(1, 0), Ground::Soil(true), grass()),
+            (Tile(-1, 0), Ground::Water, Plant::Empty),
+            (Tile(0, 1), Ground::Rock(false), Plant::Empty),
+            (Tile(0, -1), Ground::Empty, Plant::Empty),
+            (Tile(0, -2), Ground::Soil(true), grass()),
+        ];
+        let rules = PlayerRules::default();
+        let mut found = abilities(&[]).neighbours(Tile(0, 0), &board(&tiles), &rules);
+        found.sort_by_key(|Tile(x, y)| (*x, *y));
+        assert_eq!(found, vec![Tile(1, 0)]);
+
+        let all = abilities(&[Ability::Swim, Ability::Float, Ability::Climb]);
+        let mut found = all.neighbours(Tile(0, 0), &board(&tiles), &rules);
+        found.sort_by_key(|Tile(x, y)| (*x, *y));
+        assert_eq!(
+            found,
+            vec![Tile(-1, 0), Tile(0, -2), Tile(0, 1), Tile(1, 0)]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Layered music. The level's track always plays, with a growth layer that swells as plants
+//! cover the board and a tension layer that comes in while anything is burning.
+
+use bevy::prelude::*;
+
+use crate::{
+    assets::GameAssets,
+    level_asset::{CurrentLevel, LevelAsset},
+    settings::Settings,
+    states::AppState,
+    tile::{Ground, Plant},
+};
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicLayers>()
+            .add_system(start_music.in_schedule(OnExit(AppState::LoadingAssets)))
+            .add_system(level_music.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(default_music.in_schedule(OnEnter(AppState::Menu)))
+            .add_system(adapt_layers);
+    }
+}
+
+pub const MUSIC_VOLUME: f32 = 0.8;
+pub const LAYER_VOLUME: f32 = 0.6;
+
+/// How quickly the layers fade towards their new volume, per second.
+const LAYER_FADE_SPEED: f32 = 0.5;
+
+/// The share of the board that needs to be covered in plants for the growth layer to be at full volume.
+const FULL_GROWTH: f32 = 0.5;
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct MusicLayers {
+    track: Option<Handle<AudioSource>>,
+    base: Option<Handle<AudioSink>>,
+    growth: Option<Handle<AudioSink>>,
+    tension: Option<Handle<AudioSink>>,
+    growth_volume: f32,
+    tension_volume: f32,
+}
+
+fn play_loop(
+    audio: &Audio,
+    sinks: &Assets<AudioSink>,
+    source: Handle<AudioSource>,
+    volume: f32,
+) -> Handle<AudioSink> {
+    let sink = audio.play_with_settings(source, PlaybackSettings::LOOP.with_volume(volume));
+    sinks.get_handle(sink)
+}
+
+fn switch_track(
+    layers: &mut MusicLayers,
+    track: Handle<AudioSource>,
+    assets: &GameAssets,
+    audio: &Audio,
+    sinks: &Assets<AudioSink>,
+) {
+    if layers.track.as_ref() == Some(&track) {
+        return;
+    }
+    for sink in [&layers.base, &layers.growth, &layers.tension] {
+        if let Some(sink) = sink.as_ref().and_then(|sink| sinks.get(sink)) {
+            sink.stop();
+        }
+    }
+    // The layers start over with the track, so they stay in time with it.
+    layers.base = Some(play_loop(audio, sinks, track.clone(), MUSIC_VOLUME));
+    layers.growth = Some(play_loop(
+        audio,
+        sinks,
+        assets.music_growth.clone(),
+        layers.growth_volume,
+    ));
+    layers.tension = Some(play_loop(
+        audio,
+        sinks,
+        assets.music_tension.clone(),
+        layers.tension_volume,
+    ));
+    layers.track = Some(track);
+}
+
+fn start_music(
+    audio: Option<Res<Audio>>,
+    sinks: Option<Res<Assets<AudioSink>>>,
+    assets: Res<GameAssets>,
+    mut layers: ResMut<MusicLayers>,
+) {
+    let (Some(audio), Some(sinks)) = (audio, sinks) else { return; };
+
+    switch_track(&mut layers, assets.music.clone(), &assets, &audio, &sinks);
+}
+
+fn level_music(
+    audio: Option<Res<Audio>>,
+    sinks: Option<Res<Assets<AudioSink>>>,
+    assets: Res<GameAssets>,
+    server: Res<AssetServer>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<LevelAsset>>,
+    mut layers: ResMut<MusicLayers>,
+) {
+    let (Some(audio), Some(sinks)) = (audio, sinks) else { return; };
+    let track = current_level
+        .0
+        .as_ref()
+        .and_then(|handle| levels.get(handle))
+        .and_then(|level| level.music.as_ref())
+        .map(|music| server.load(music.as_str()))
+        .unwrap_or_else(|| assets.music.clone());
+    switch_track(&mut layers, track, &assets, &audio, &sinks);
+}
+
+fn default_music(
+    audio: Option<Res<Audio>>,
+    sinks: Option<Res<Assets<AudioSink>>>,
+    assets: Res<GameAssets>,
+    mut layers: ResMut<MusicLayers>,
+) {
+    let (Some(audio), Some(sinks)) = (audio, sinks) else { return; };
+    switch_track(&mut layers, assets.music.clone(), &assets, &audio, &sinks);
+}
+
+fn adapt_layers(
+    mut layers: ResMut<MusicLayers>,
+    sinks: Option<Res<Assets<AudioSink>>>,
+    state: Res<State<AppState>>,
+    tiles: Query<(&Ground, &Plant)>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+) {
+    let Some(sinks) = sinks else { return; };
+    let (growth, tension) = if state.0 == AppState::InGame {
+        let board = tiles.iter().filter(|(g, _)| **g != Ground::Empty).count();
+        let plants = tiles
+            .iter()
+            .filter(|(_, p)| matches!(p, Plant::Plant(_)))
+            .count();
+        let burning = tiles.iter().any(|(_, p)| matches!(p, Plant::Fire(_)));
+        let coverage = plants as f32 / board.max(1) as f32;
+        (
+            (coverage / FULL_GROWTH).min(1.) * LAYER_VOLUME,
+            if burning { LAYER_VOLUME } else { 0. },
+        )
+    } else {
+        (0., 0.)
+    };
+
+    // Fading changes the volume every frame, so the resource itself isn't marked as changed.
+    let layers = layers.bypass_change_detection();
+    let fade = LAYER_FADE_SPEED * time.delta_seconds();
+    layers.growth_volume = approach(layers.growth_volume, growth, fade);
+    layers.tension_volume = approach(layers.tension_volume, tension, fade);
+
+    let volume = settings.music_volume();
+    if let Some(sink) = layers.base.as_ref().and_then(|sink| sinks.get(sink)) {
+        sink.set_volume(MUSIC_VOLUME * volume);
+    }
+    if let Some(sink) = layers.growth.as_ref().and_then(|sink| sinks.get(sink)) {
+        sink.set_volume(layers.growth_volume * volume);
+    }
+    if let Some(sink) = layers.tension.as_ref().and_then(|sink| sinks.get(sink)) {
+        sink.set_volume(layers.tension_volume * volume);
+    }
+}
+
+fn approach(current: f32, target: f32, step: f32) -> f32 {
+    if current < target {
+        (current + step).min(target)
+    } else {
+        (current - step).max(target)
+    }
+}
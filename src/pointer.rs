@@ -0,0 +1,221 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{
+    prelude::*,
+    time::common_conditions::on_timer,
+    utils::{HashMap, HashSet},
+    window::PrimaryWindow,
+};
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    almanac::AlmanacOpen,
+    camera::{minimap_area, CameraView},
+    control::{Action, AvailablePowers, Player, Power, PowerTarget},
+    hazards::PlayerRules,
+    history::record_history,
+    movement::Abilities,
+    states::AppState,
+    tile::{Ground, Plant, Tile, TILE_WORLD_SIZE},
+};
+
+pub struct PointerPlugin;
+
+impl Plugin for PointerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ArmedPower>()
+            .add_event::<ArmPower>()
+            .add_system(arm_power.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                pointer_input
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(arm_power)
+                    .before(record_history),
+            )
+            .add_system(
+                follow_path.before(record_history).run_if(
+                    in_state(AppState::InGame)
+                        .and_then(on_timer(Duration::from_secs_f32(PATH_STEP_SECONDS))),
+                ),
+            )
+            .add_system(reset_armed_power.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+const PATH_STEP_SECONDS: f32 = 0.15;
+
+/// The power picked from the cards, waiting for a target tile to be clicked.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArmedPower(pub Option<Power>);
+
+/// Sent when a power card is clicked - arms the power, or disarms it if it was already armed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArmPower(pub Power);
+
+/// Tiles the player is still walking through after a click, next step first.
+#[derive(Component, Debug, Clone, Default)]
+pub struct PlayerPath(pub VecDeque<Tile>);
+
+/// Converts a position in world space to the tile drawn there.
+pub fn world_to_tile(position: Vec2) -> Tile {
+    Tile::from(position / TILE_WORLD_SIZE + Vec2::splat(0.5))
+}
+
+fn reset_armed_power(mut commands: Commands) {
+    commands.insert_resource(ArmedPower::default());
+}
+
+fn arm_power(mut events: EventReader<ArmPower>, mut armed: ResMut<ArmedPower>) {
+    for ArmPower(power) in events.iter() {
+        if armed.0.as_ref() == Some(power) {
+            armed.0 = None;
+        } else {
+            armed.0 = Some(power.clone());
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn pointer_input(
+    mut commands: Commands,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mouse: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    interactions: Query<&Interaction>,
+    mut players: Query<(Entity, &Player, &mut ActionState<Action>)>,
+    tiles: Query<(&Tile, &Ground, &Plant)>,
+    mut armed: ResMut<ArmedPower>,
+    mut power_target: ResMut<PowerTarget>,
+    powers: Res<AvailablePowers>,
+    abilities: Res<Abilities>,
+    rules: Res<PlayerRules>,
+    almanac: Res<AlmanacOpen>,
+    view: Res<CameraView>,
+) {
+    // The level can't be clicked through the almanac.
+    if almanac.0 {
+        return;
+    }
+    let Ok(window) = windows.get_single() else { return; };
+    let Ok((camera, camera_transform)) = cameras.get_single() else { return; };
+
+    let position = if mouse.just_pressed(MouseButton::Left) {
+        window.cursor_position()
+    } else {
+        touches
+            .iter_just_pressed()
+            .next()
+            .map(|touch| touch.position())
+    };
+    let Some(position) = position else { return; };
+
+    // Clicks on the cards and buttons are handled by the UI.
+    if interactions.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+
+    // Nor through the minimap.
+    if minimap_area(&view, window).map_or(false, |area| area.contains(position)) {
+        return;
+    }
+
+    let Some(position) = camera.viewport_to_world_2d(camera_transform, position) else { return; };
+    let clicked = world_to_tile(position);
+
+    let Ok((entity, player, mut actions)) = players.get_single_mut() else { return; };
+    let from = Tile(player.0, player.1);
+
+    if let Some(power) = armed.0.take() {
+        let in_range = clicked.0.abs_diff(from.0).max(clicked.1.abs_diff(from.1)) <= power.range();
+        if in_range && powers.0.contains_key(&power) {
+            power_target.0 = Some(clicked);
+            actions.press(power.action());
+        }
+        return;
+    }
+
+    let tiles = tiles
+        .iter()
+        .map(|(t, g, p)| (*t, (g, p)))
+        .collect::<HashMap<_, _>>();
+
+    if let Some(path) = find_path(from, clicked, &tiles, &abilities, &rules) {
+        commands.entity(entity).insert(PlayerPath(path));
+    }
+}
+
+pub fn follow_path(
+    mut commands: Commands,
+    mut players: Query<(Entity, &Player, &mut PlayerPath, &mut ActionState<Action>)>,
+    tiles: Query<(&Tile, &Ground, &Plant)>,
+    abilities: Res<Abilities>,
+    rules: Res<PlayerRules>,
+) {
+    let tiles = tiles
+        .iter()
+        .map(|(t, g, p)| (*t, (g, p)))
+        .collect::<HashMap<_, _>>();
+
+    for (entity, player, mut path, mut actions) in players.iter_mut() {
+        let from = Tile(player.0, player.1);
+        let Some(next) = path.0.pop_front() else {
+            commands.entity(entity).remove::<PlayerPath>();
+            continue;
+        };
+
+        // The board keeps changing underneath us, so give up if the next step is no longer open.
+        let direction = ((next.0 - from.0).signum(), (next.1 - from.1).signum());
+        if abilities.step(from, direction, &tiles, &rules) != Some(next) {
+            commands.entity(entity).remove::<PlayerPath>();
+            continue;
+        }
+
+        actions.press(match direction {
+            (0, 1) => Action::Up,
+            (0, -1) => Action::Down,
+            (-1, 0) => Action::Left,
+            _ => Action::Right,
+        });
+    }
+}
+
+/// Breadth first search across the tiles the player can currently step between.
+/// The returned path excludes the starting tile.
+pub fn find_path(
+    from: Tile,
+    to: Tile,
+    tiles: &HashMap<Tile, (&Ground, &Plant)>,
+    abilities: &Abilities,
+    rules: &PlayerRules,
+) -> Option<VecDeque<Tile>> {
+    if from == to {
+        return None;
+    }
+
+    let mut came_from = HashMap::<Tile, Tile>::default();
+    let mut visited = HashSet::<Tile>::default();
+    let mut queue = VecDeque::from([from]);
+    visited.insert(from);
+
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = VecDeque::new();
+            let mut step = current;
+            while step != from {
+                path.push_front(step);
+                step = came_from[&step];
+            }
+            return Some(path);
+        }
+
+        for next in abilities.neighbours(current, tiles, rules) {
+            if visited.insert(next) {
+                came_from.insert(next, current);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
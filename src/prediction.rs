@@ -0,0 +1,213 @@
+//! An overlay showing what the next few generations will do to the board,
+//! worked out by running the tile rules ahead on a copy of the live tiles.
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_vector_shapes::prelude::*;
+
+use crate::{
+    bindings::action_just_pressed,
+    colors::Palette,
+    control::Action,
+    generate_tiles::LevelLoaded,
+    settings::Settings,
+    states::AppState,
+    tile::{Ground, Plant, PlantDefinitions, Tile, TILE_WORLD_SIZE},
+    update_tiles::{update_tile, NEIGHBOURHOOD},
+};
+
+pub struct PredictionPlugin;
+
+impl Plugin for PredictionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Prediction>()
+            .add_event::<TogglePrediction>()
+            .add_system(
+                send_toggle.run_if(
+                    in_state(AppState::InGame).and_then(action_just_pressed(Action::Predict)),
+                ),
+            )
+            .add_system(toggle_prediction.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                predict
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(toggle_prediction),
+            )
+            .add_system(
+                draw_prediction
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(predict),
+            )
+            .add_system(
+                clear_prediction
+                    .run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            );
+    }
+}
+
+/// How many generations ahead the overlay looks.
+pub const PREDICTED_GENERATIONS: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PredictedChange {
+    /// A plant will appear, spreading from `from` if one of its neighbours is the same plant.
+    Growth { plant: String, from: Option<Tile> },
+    /// A plant will die off without burning.
+    Wilt,
+    /// A plant will catch fire.
+    Fire,
+}
+
+/// The first predicted change to each tile, and the generation it happens in, counting from 1.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Prediction {
+    pub visible: bool,
+    pub changes: Vec<(Tile, usize, PredictedChange)>,
+}
+
+/// Shows or hides the prediction overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TogglePrediction;
+
+fn send_toggle(mut events: EventWriter<TogglePrediction>) {
+    events.send(TogglePrediction);
+}
+
+fn toggle_prediction(
+    mut events: EventReader<TogglePrediction>,
+    mut prediction: ResMut<Prediction>,
+) {
+    for _ in events.iter() {
+        prediction.visible = !prediction.visible;
+    }
+}
+
+fn clear_prediction(mut prediction: ResMut<Prediction>) {
+    prediction.changes.clear();
+}
+
+type ChangedTile = Or<(Changed<Ground>, Changed<Plant>)>;
+
+fn predict(
+    mut prediction: ResMut<Prediction>,
+    tiles: Query<(&Tile, &Ground, &Plant)>,
+    changed: Query<(), ChangedTile>,
+    plants: Res<PlantDefinitions>,
+) {
+    if !prediction.visible || (changed.is_empty() && !prediction.is_changed()) {
+        return;
+    }
+
+    let mut state = tiles
+        .iter()
+        .map(|(t, g, p)| (*t, (*g, p.clone())))
+        .collect::<HashMap<_, _>>();
+    let mut changes = HashMap::<Tile, (usize, PredictedChange)>::default();
+
+    for generation in 1..=PREDICTED_GENERATIONS {
+        let current = state
+            .iter()
+            .map(|(t, (g, p))| (*t, (g, p)))
+            .collect::<HashMap<_, _>>();
+        let next = current
+            .iter()
+            .map(|(tile, (ground, plant))| {
+                (*tile, update_tile(ground, plant, tile, &current, &plants))
+            })
+            .collect::<HashMap<_, _>>();
+
+        for (tile, (_, plant)) in next.iter() {
+            let Some((_, old_plant)) = current.get(tile) else { continue; };
+            if changes.contains_key(tile) || **old_plant == *plant {
+                continue;
+            }
+            let change = match (old_plant, plant) {
+                (Plant::Fire(_), _) => continue,
+                (_, Plant::Fire(_)) => PredictedChange::Fire,
+                (_, Plant::Plant(id)) => PredictedChange::Growth {
+                    plant: id.clone(),
+                    from: NEIGHBOURHOOD
+                        .iter()
+                        .map(|(x, y)| Tile(tile.0 + x, tile.1 + y))
+                        .find(|t| matches!(current.get(t), Some((_, Plant::Plant(p))) if p == id)),
+                },
+                (_, Plant::Empty) => PredictedChange::Wilt,
+            };
+            changes.insert(*tile, (generation, change));
+        }
+
+        state = next;
+    }
+
+    let mut changes = changes
+        .into_iter()
+        .map(|(tile, (generation, change))| (tile, generation, change))
+        .collect::<Vec<_>>();
+    changes.sort_by_key(|(t, generation, _)| (*generation, t.0, t.1));
+    prediction.changes = changes;
+}
+
+fn tile_center(tile: Tile) -> Vec3 {
+    Vec3::new(tile.0 as f32, tile.1 as f32, 0.) * TILE_WORLD_SIZE
+}
+
+fn draw_prediction(
+    mut painter: ShapePainter,
+    prediction: Res<Prediction>,
+    plants: Res<PlantDefinitions>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+) {
+    if !prediction.visible {
+        return;
+    }
+
+    for (tile, generation, change) in prediction.changes.iter() {
+        // Later generations are drawn fainter, since more can change before then.
+        let alpha = 0.6 / *generation as f32;
+        let center = tile_center(*tile) + Vec3::Z * 4.;
+
+        match change {
+            PredictedChange::Growth { plant, from } => {
+                let color = if plants.get(plant).is_some() {
+                    plants.color(plant, settings.plant_palette)
+                } else {
+                    palette.light
+                };
+
+                painter.hollow = false;
+                painter.set_translation(center);
+                painter.color = color.with_a(alpha);
+                painter.circle(TILE_WORLD_SIZE * 0.3);
+
+                if let Some(from) = from {
+                    let start = tile_center(*from) + Vec3::Z * 4.;
+                    let direction = (center - start).normalize_or_zero();
+                    let end = center - direction * TILE_WORLD_SIZE * 0.3;
+                    let head = direction.truncate().perp().extend(0.) * TILE_WORLD_SIZE * 0.1;
+
+                    painter.set_translation(Vec3::ZERO);
+                    painter.color = palette.pale.with_a(alpha);
+                    painter.thickness = 3.;
+                    painter.line(start + direction * TILE_WORLD_SIZE * 0.3, end);
+                    painter.line(end, end - direction * TILE_WORLD_SIZE * 0.15 + head);
+                    painter.line(end, end - direction * TILE_WORLD_SIZE * 0.15 - head);
+                }
+            }
+            PredictedChange::Wilt => {
+                painter.hollow = true;
+                painter.thickness = 3.;
+                painter.set_translation(center);
+                painter.color = palette.secondary.with_a(alpha);
+                painter.circle(TILE_WORLD_SIZE * 0.3);
+            }
+            PredictedChange::Fire => {
+                painter.hollow = true;
+                painter.thickness = 4.;
+                painter.set_translation(center);
+                painter.color = palette.danger.with_a(alpha + 0.2);
+                painter.rect(Vec2::splat(TILE_WORLD_SIZE * 0.9));
+            }
+        }
+    }
+    painter.hollow = false;
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::SimulationClock,
+    control::{Player, Power},
+    generate_tiles::LevelLoaded,
+    history::PendingRestore,
+    level_asset::{level_file_name, CurrentLevel, LevelAsset},
+    score::{BonusObjective, Score},
+    states::AppState,
+    storage,
+    target::LevelCompleted,
+    tile::{Plant, Tile},
+    update_tiles::{use_powers, Generation, PowerApplied},
+};
+
+pub struct ProgressPlugin;
+
+impl Plugin for ProgressPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<Progress>(PROGRESS_KEY).unwrap_or_default())
+            .init_resource::<LevelStats>()
+            .init_resource::<LastResult>()
+            .add_system(reset_stats.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(
+                reset_stats.run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            )
+            .add_system(
+                track_stats
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(use_powers),
+            )
+            .add_system(
+                record_completion
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame).and_then(on_event::<LevelCompleted>())),
+            );
+    }
+}
+
+const PROGRESS_KEY: &str = "progress";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelProgress {
+    pub completed: bool,
+    pub best_moves: Option<usize>,
+    pub best_time: Option<f32>,
+    #[serde(default)]
+    pub best_stars: usize,
+}
+
+/// Completion records for every level played, keyed by the level's file name.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Progress(pub BTreeMap<String, LevelProgress>);
+
+impl Progress {
+    pub fn get(&self, level: &str) -> Option<&LevelProgress> {
+        self.0.get(level)
+    }
+
+    pub fn is_completed(&self, level: &str) -> bool {
+        self.get(level).map(|p| p.completed).unwrap_or_default()
+    }
+
+    /// A level is unlocked once the level listed before it has been completed.
+    pub fn is_unlocked(&self, levels: &[String], index: usize) -> bool {
+        index == 0
+            || levels
+                .get(index - 1)
+                .map(|previous| self.is_completed(previous))
+                .unwrap_or(true)
+    }
+
+    pub fn save(&self) {
+        storage::save(PROGRESS_KEY, self);
+    }
+}
+
+/// Moves, powers used, hints taken and time for the level currently being played.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelStats {
+    pub moves: usize,
+    pub time: f32,
+    pub powers_used: BTreeMap<Power, usize>,
+    pub hints: usize,
+    last_position: Option<Tile>,
+}
+
+/// How the most recently completed level went, for the level complete screen.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct LastResult {
+    pub level: String,
+    pub score: Score,
+    pub time: f32,
+    pub new_best: bool,
+    /// The level's bonus objectives, and whether each was completed.
+    pub bonus: Vec<(BonusObjective, bool)>,
+}
+
+/// Starts the stats over for a fresh level. Undo and resume keep the stats already there,
+/// but not where the player was, since the restored position isn't a move.
+fn reset_stats(
+    pending: Res<PendingRestore>,
+    mut stats: ResMut<LevelStats>,
+    mut commands: Commands,
+) {
+    if pending.0.is_none() {
+        commands.insert_resource(LevelStats::default());
+    } else {
+        stats.last_position = None;
+    }
+}
+
+fn track_stats(
+    mut stats: ResMut<LevelStats>,
+    players: Query<&Player>,
+    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    pending: Res<PendingRestore>,
+    mut applied: EventReader<PowerApplied>,
+) {
+    if !clock.paused {
+        stats.time += time.delta_seconds();
+    }
+    for PowerApplied(power, _) in applied.iter() {
+        *stats.powers_used.entry(power.clone()).or_default() += 1;
+    }
+
+    // The player is only put back where they were once the restore has been applied.
+    if pending.0.is_some() {
+        return;
+    }
+    let Ok(player) = players.get_single() else { return; };
+    let position = Tile(player.0, player.1);
+    match stats.last_position {
+        Some(last) if last != position => {
+            stats.moves += 1;
+            stats.last_position = Some(position);
+        }
+        None => stats.last_position = Some(position),
+        _ => {}
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_completion(
+    current_level: Res<CurrentLevel>,
+    server: Res<AssetServer>,
+    levels: Res<Assets<LevelAsset>>,
+    stats: Res<LevelStats>,
+    generation: Res<Generation>,
+    plants: Query<&Plant>,
+    mut progress: ResMut<Progress>,
+    mut commands: Commands,
+) {
+    let Some(handle) = current_level.0.as_ref() else { return; };
+    let Some(level) = level_file_name(&server, handle) else { return; };
+
+    let mut plant_counts = BTreeMap::<String, usize>::new();
+    for plant in plants.iter() {
+        if let Plant::Plant(id) = plant {
+            *plant_counts.entry(id.clone()).or_default() += 1;
+        }
+    }
+    let par = levels
+        .get(handle)
+        .map(|asset| asset.par.clone())
+        .unwrap_or_default();
+    let bonus = par
+        .bonus
+        .iter()
+        .map(|bonus| {
+            let complete = bonus.is_complete(&stats.powers_used, &plant_counts);
+            (bonus.clone(), complete)
+        })
+        .collect();
+    let score = Score::new(
+        &par,
+        stats.moves,
+        &stats.powers_used,
+        generation.0,
+        &plant_counts,
+        stats.hints,
+    );
+
+    let record = progress.0.entry(level.clone()).or_default();
+    let new_best = record.best_moves.map(|m| stats.moves < m).unwrap_or(true)
+        || record.best_time.map(|t| stats.time < t).unwrap_or(true);
+
+    record.completed = true;
+    record.best_moves = Some(
+        record
+            .best_moves
+            .map_or(stats.moves, |m| m.min(stats.moves)),
+    );
+    record.best_time = Some(record.best_time.map_or(stats.time, |t| t.min(stats.time)));
+    record.best_stars = record.best_stars.max(score.stars);
+    progress.save();
+
+    commands.insert_resource(LastResult {
+        level,
+        score,
+        time: stats.time,
+        new_best,
+        bonus,
+    });
+}
+
+pub fn format_time(seconds: f32) -> String {
+    let seconds = seconds.round() as usize;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
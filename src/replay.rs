@@ -0,0 +1,382 @@
+//! Records every action taken in a level along with the generation it happened in,
+//! and plays those recordings back against the same level.
+//! Running the game with `--replay <file>` plays a saved replay straight away and exits once it ends.
+//! Add `--headless` to play it without a window, exiting with a non-zero code if the level didn't
+//! end the way it did when it was recorded.
+
+use bevy::{app::AppExit, prelude::*};
+use leafwing_input_manager::{prelude::*, Actionlike};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    control::{move_player, Action, Player, PowerTarget},
+    failure::{FailureReason, LevelFailure},
+    generate_tiles::LevelLoaded,
+    history::{record_history, LevelSnapshot, PendingRestore},
+    level_asset::{level_file_name, CurrentLevel},
+    pointer::{follow_path, pointer_input},
+    states::AppState,
+    storage,
+    target::LevelCompleted,
+    tile::Tile,
+    update_tiles::Generation,
+};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recording>()
+            .init_resource::<Playback>()
+            .insert_resource(PendingReplay(replay_from_args()))
+            .add_event::<WatchReplay>()
+            .add_event::<PlaybackFinished>()
+            .add_system(watch_replay)
+            .add_system(exit_after_playback.in_base_set(CoreSet::Last))
+            .add_system(start_replay.in_set(OnUpdate(AppState::Menu)))
+            .add_system(start_replay.in_set(OnUpdate(AppState::LevelComplete)))
+            .add_system(
+                start_recording
+                    .run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            )
+            .add_system(
+                play_replay
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(pointer_input)
+                    .after(follow_path)
+                    .before(record_history),
+            )
+            .add_system(
+                record_actions
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(play_replay)
+                    .before(move_player),
+            )
+            .add_system(
+                record_completion
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame).and_then(on_event::<LevelCompleted>())),
+            )
+            .add_system(record_failure.in_schedule(OnEnter(AppState::LevelFailed)))
+            .add_system(save_recording.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+/// How many generations a replay keeps running after its last action before giving up on reaching its outcome.
+const REPLAY_TIMEOUT_GENERATIONS: usize = 50;
+
+/// The actions that were just pressed in a single frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayStep {
+    pub generation: usize,
+    pub actions: Vec<Action>,
+    pub target: Option<Tile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayOutcome {
+    Completed {
+        generation: usize,
+    },
+    Failed {
+        generation: usize,
+        reason: FailureReason,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub level: String,
+    /// The state the level was restored to before the first step, if it didn't start fresh.
+    pub start: Option<LevelSnapshot>,
+    pub steps: Vec<ReplayStep>,
+    pub outcome: Option<ReplayOutcome>,
+}
+
+/// The replay being recorded for the current level.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Recording(pub Replay);
+
+/// A replay waiting to start once the menu or level complete screen is showing.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct PendingReplay(pub Option<(Replay, bool)>);
+
+#[derive(Debug, Clone)]
+pub struct ActivePlayback {
+    pub replay: Replay,
+    pub next: usize,
+    waiting: bool,
+    exit_when_done: bool,
+}
+
+/// The replay currently driving the player, if any.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Playback(pub Option<ActivePlayback>);
+
+/// Plays back the replay recorded for the last level played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchReplay;
+
+/// Sent when a replay stops playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaybackFinished {
+    /// Whether the level ended the way it did when the replay was recorded.
+    pub matched: bool,
+    exit: bool,
+}
+
+fn replay_key(level: &str) -> String {
+    format!("replay_{}", level.replace(".lvl.yaml", ""))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn replay_from_args() -> Option<(Replay, bool)> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--replay").skip(1);
+    let path = args.next()?;
+    let file = match std::fs::read_to_string(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Couldn't read replay {path}: {e}");
+            std::process::exit(2);
+        }
+    };
+    match serde_json::from_str(&file) {
+        Ok(replay) => Some((replay, true)),
+        Err(e) => {
+            error!("Couldn't parse replay {path}: {e}");
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn replay_from_args() -> Option<(Replay, bool)> {
+    None
+}
+
+/// Run condition for the simulation - while a replay is playing, a generation only
+/// runs once every action recorded before it has been played.
+pub fn generation_ready(playback: Res<Playback>, generation: Res<Generation>) -> bool {
+    let Some(active) = playback.0.as_ref() else { return true; };
+    !active.waiting
+        && active
+            .replay
+            .steps
+            .get(active.next)
+            .map(|step| step.generation > generation.0)
+            .unwrap_or(true)
+}
+
+fn watch_replay(
+    mut events: EventReader<WatchReplay>,
+    recording: Res<Recording>,
+    mut pending: ResMut<PendingReplay>,
+) {
+    if events.iter().last().is_none() {
+        return;
+    }
+    pending.0 = Some((recording.0.clone(), false));
+}
+
+fn start_replay(
+    mut pending: ResMut<PendingReplay>,
+    mut playback: ResMut<Playback>,
+    server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Some((replay, exit_when_done)) = pending.0.take() else { return; };
+    info!("Playing replay of {}", replay.level);
+
+    let url = format!("levels/{}", replay.level);
+    commands.insert_resource(CurrentLevel(Some(server.load(url))));
+    commands.insert_resource(PendingRestore(replay.start.clone()));
+    commands.insert_resource(NextState(Some(AppState::LoadingLevel)));
+    playback.0 = Some(ActivePlayback {
+        replay,
+        next: 0,
+        waiting: false,
+        exit_when_done,
+    });
+}
+
+fn start_recording(
+    mut recording: ResMut<Recording>,
+    playback: Res<Playback>,
+    current_level: Res<CurrentLevel>,
+    server: Res<AssetServer>,
+    pending: Res<PendingRestore>,
+) {
+    if playback.0.is_some() {
+        return;
+    }
+    // Levels that weren't loaded from a file, like editor play tests, aren't recorded.
+    let level = current_level
+        .0
+        .as_ref()
+        .and_then(|level| level_file_name(&server, level))
+        .unwrap_or_default();
+
+    recording.0 = Replay {
+        level,
+        start: pending.0.clone(),
+        ..default()
+    };
+}
+
+fn play_replay(
+    mut playback: ResMut<Playback>,
+    mut players: Query<&mut ActionState<Action>, With<Player>>,
+    mut power_target: ResMut<PowerTarget>,
+    generation: Res<Generation>,
+    mut finished: EventWriter<PlaybackFinished>,
+) {
+    let Some(active) = playback.0.as_mut() else { return; };
+    let Ok(mut actions) = players.get_single_mut() else { return; };
+
+    // Live input is ignored while a replay is playing.
+    for action in Action::variants() {
+        actions.release(action);
+    }
+    power_target.0 = None;
+
+    let step = active
+        .replay
+        .steps
+        .get(active.next)
+        .filter(|step| step.generation <= generation.0)
+        .cloned();
+    let Some(step) = step else {
+        active.waiting = false;
+        let last_generation = active
+            .replay
+            .steps
+            .last()
+            .map(|step| step.generation)
+            .unwrap_or_default();
+        if active.next >= active.replay.steps.len()
+            && generation.0 > last_generation + REPLAY_TIMEOUT_GENERATIONS
+        {
+            finish_playback(&mut playback, None, &mut finished);
+        }
+        return;
+    };
+
+    for action in step.actions.iter() {
+        actions.press(*action);
+    }
+    power_target.0 = step.target;
+    active.next += 1;
+    active.waiting = true;
+}
+
+fn record_actions(
+    mut recording: ResMut<Recording>,
+    playback: Res<Playback>,
+    players: Query<&ActionState<Action>, With<Player>>,
+    power_target: Res<PowerTarget>,
+    generation: Res<Generation>,
+) {
+    if playback.0.is_some() || recording.0.level.is_empty() {
+        return;
+    }
+    let Ok(actions) = players.get_single() else { return; };
+    let mut pressed = actions.get_just_pressed();
+    pressed.retain(Action::plays);
+    if pressed.is_empty() {
+        return;
+    }
+
+    recording.0.steps.push(ReplayStep {
+        generation: generation.0,
+        actions: pressed,
+        target: power_target.0,
+    });
+}
+
+fn record_completion(
+    mut recording: ResMut<Recording>,
+    mut playback: ResMut<Playback>,
+    generation: Res<Generation>,
+    mut finished: EventWriter<PlaybackFinished>,
+) {
+    let outcome = ReplayOutcome::Completed {
+        generation: generation.0,
+    };
+    if playback.0.is_some() {
+        finish_playback(&mut playback, Some(outcome), &mut finished);
+    } else {
+        recording.0.outcome = Some(outcome);
+    }
+}
+
+fn record_failure(
+    mut recording: ResMut<Recording>,
+    mut playback: ResMut<Playback>,
+    generation: Res<Generation>,
+    failure: Option<Res<LevelFailure>>,
+    mut finished: EventWriter<PlaybackFinished>,
+) {
+    let Some(failure) = failure else { return; };
+    let outcome = ReplayOutcome::Failed {
+        generation: generation.0,
+        reason: failure.reason.clone(),
+    };
+    if playback.0.is_some() {
+        finish_playback(&mut playback, Some(outcome), &mut finished);
+    } else if !recording.0.level.is_empty() {
+        recording.0.outcome = Some(outcome);
+        storage::save(&replay_key(&recording.0.level), &recording.0);
+    }
+}
+
+fn save_recording(
+    recording: Res<Recording>,
+    mut playback: ResMut<Playback>,
+    state: Res<State<AppState>>,
+) {
+    if playback.0.is_some() {
+        // Leaving the level part way through a replay stops it, failing is checked on the failure screen.
+        if state.0 != AppState::LevelFailed {
+            playback.0 = None;
+        }
+        return;
+    }
+    if recording.0.level.is_empty() {
+        return;
+    }
+    storage::save(&replay_key(&recording.0.level), &recording.0);
+}
+
+fn finish_playback(
+    playback: &mut Playback,
+    outcome: Option<ReplayOutcome>,
+    finished: &mut EventWriter<PlaybackFinished>,
+) {
+    let Some(active) = playback.0.take() else { return; };
+    let matched = active.replay.outcome == outcome;
+    if matched {
+        info!(
+            "Replay of {} ended as recorded: {outcome:?}",
+            active.replay.level
+        );
+    } else {
+        warn!(
+            "Replay of {} ended with {outcome:?}, but was recorded with {:?}",
+            active.replay.level, active.replay.outcome
+        );
+    }
+
+    finished.send(PlaybackFinished {
+        matched,
+        exit: active.exit_when_done,
+    });
+}
+
+fn exit_after_playback(
+    mut finished: EventReader<PlaybackFinished>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if finished.iter().any(|finished| finished.exit) {
+        exit.send(AppExit);
+    }
+}
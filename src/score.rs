@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{control::Power, locale::Strings, tile::PlantDefinitions};
+
+pub const MAX_STARS: usize = 3;
+
+/// The par values a level is scored against. Any value left out is always met,
+/// but a level needs at least one for its par to be met at all.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Par {
+    pub moves: Option<usize>,
+    pub powers: Option<usize>,
+    pub generations: Option<usize>,
+    pub bonus: Vec<BonusObjective>,
+}
+
+/// Optional extra goals a level can set for the third star.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "objective", rename_all = "snake_case")]
+pub enum BonusObjective {
+    /// Finish without ever using a power.
+    Unused { power: Power },
+    /// Finish with at least `count` tiles of a plant on the board.
+    Grow { plant: String, count: usize },
+}
+
+impl BonusObjective {
+    pub fn is_complete(
+        &self,
+        powers_used: &BTreeMap<Power, usize>,
+        plant_counts: &BTreeMap<String, usize>,
+    ) -> bool {
+        match self {
+            BonusObjective::Unused { power } => {
+                powers_used.get(power).copied().unwrap_or_default() == 0
+            }
+            BonusObjective::Grow { plant, count } => {
+                plant_counts.get(plant).copied().unwrap_or_default() >= *count
+            }
+        }
+    }
+
+    pub fn describe(&self, plants: &PlantDefinitions, strings: &Strings) -> String {
+        match self {
+            BonusObjective::Unused { power } => strings.format(
+                "bonus.unused",
+                &[("power", &strings.get(power.label_key()))],
+            ),
+            BonusObjective::Grow { plant, count } => strings.format(
+                "bonus.grow",
+                &[("count", count), ("plant", &plants.name(plant))],
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Score {
+    pub moves: usize,
+    pub powers: usize,
+    pub generations: usize,
+    pub bonus_completed: usize,
+    pub bonus_total: usize,
+    #[serde(default)]
+    pub hints: usize,
+    pub stars: usize,
+}
+
+impl Score {
+    /// One star for finishing, a second for meeting every par value,
+    /// and a third for also completing every bonus objective, if the level has any.
+    /// Each hint taken costs a star, though finishing is always worth at least one.
+    pub fn new(
+        par: &Par,
+        moves: usize,
+        powers_used: &BTreeMap<Power, usize>,
+        generations: usize,
+        plant_counts: &BTreeMap<String, usize>,
+        hints: usize,
+    ) -> Self {
+        let powers = powers_used.values().sum();
+        let bonus_completed = par
+            .bonus
+            .iter()
+            .filter(|bonus| bonus.is_complete(powers_used, plant_counts))
+            .count();
+
+        let within = |value: usize, par: Option<usize>| par.map(|p| value <= p).unwrap_or(true);
+        let has_par = par.moves.is_some() || par.powers.is_some() || par.generations.is_some();
+        let met_par = has_par
+            && within(moves, par.moves)
+            && within(powers, par.powers)
+            && within(generations, par.generations);
+        let met_bonus = !par.bonus.is_empty() && bonus_completed == par.bonus.len();
+        let stars = match (met_par, met_bonus) {
+            (true, true) => 3,
+            (true, false) => 2,
+            _ => 1,
+        };
+        let stars = stars.saturating_sub(hints).max(1);
+
+        Self {
+            moves,
+            powers,
+            generations,
+            bonus_completed,
+            bonus_total: par.bonus.len(),
+            hints,
+            stars,
+        }
+    }
+}
+
+pub fn star_label(stars: usize, strings: &Strings) -> String {
+    strings.format(
+        "stars",
+        &[("stars", &stars.min(MAX_STARS)), ("max", &MAX_STARS)],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn par(moves: Option<usize>, bonus: Vec<BonusObjective>) -> Par {
+        Par {
+            moves,
+            bonus,
+            ..Default::default()
+        }
+    }
+
+    fn stars(par: &Par, moves: usize, powers_used: &[(Power, usize)], hints: usize) -> usize {
+        let powers_used = powers_used.iter().cloned().collect();
+        Score::new(par, moves, &powers_used, 0, &BTreeMap::new(), hints).stars
+    }
+
+    fn no_fire() -> BonusObjective {
+        BonusObjective::Unused { power: Power::Fire }
+    }
+
+    #[test]
+    fn a_level_without_par_only_awards_finishing() {
+        assert_eq!(stars(&Par::default(), 1, &[], 0), 1);
+        assert_eq!(stars(&par(None, vec![no_fire()]), 1, &[], 0), 1);
+    }
+
+    #[test]
+    fn meeting_par_earns_the_second_star() {
+        let par = par(Some(5), vec![]);
+        assert_eq!(stars(&par, 5, &[], 0), 2);
+        assert_eq!(stars(&par, 6, &[], 0), 1);
+    }
+
+    #[test]
+    fn the_third_star_needs_every_bonus_objective() {
+        let par = par(Some(5), vec![no_fire()]);
+        assert_eq!(stars(&par, 5, &[(Power::Seed, 2)], 0), 3);
+        assert_eq!(stars(&par, 5, &[(Power::Fire, 1)], 0), 2);
+        assert_eq!(stars(&par, 6, &[], 0), 1);
+    }
+
+    #[test]
+    fn hints_cost_a_star_each_but_finishing_keeps_one() {
+        let par = par(Some(5), vec![no_fire()]);
+        assert_eq!(stars(&par, 5, &[], 1), 2);
+        assert_eq!(stars(&par, 5, &[], 5), 1);
+    }
+
+    #[test]
+    fn bonus_objectives_are_counted() {
+        let par = par(
+            None,
+            vec![
+                no_fire(),
+                BonusObjective::Grow {
+                    plant: "grass".to_string(),
+                    count: 3,
+                },
+            ],
+        );
+        let plant_counts = [("grass".to_string(), 2)].into_iter().collect();
+        let score = Score::new(&par, 0, &BTreeMap::new(), 0, &plant_counts, 0);
+        assert_eq!((score.bonus_completed, score.bonus_total), (1, 2));
+    }
+}
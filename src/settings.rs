@@ -0,0 +1,374 @@
+use crate::{
+    assets::GameAssets,
+    clock::{DEFAULT_SPEED, SPEEDS},
+    colors::{Palette, PlantPalette},
+    locale::{Strings, DEFAULT_LANGUAGE},
+    states::AppState,
+    storage,
+};
+use belly::{core::ess::Styles, prelude::*};
+use bevy::{prelude::*, ui::UiSystem};
+use serde::{Deserialize, Serialize};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<Settings>(SETTINGS_KEY).unwrap_or_default())
+            .init_resource::<Palette>()
+            .add_event::<SettingsEvent>()
+            .add_system(apply_settings)
+            .add_system(
+                scale_text
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(UiSystem::Flex),
+            )
+            .add_system(
+                setup_menu
+                    .in_base_set(CoreSet::PreUpdate)
+                    .run_if(in_state(AppState::Settings)),
+            )
+            .add_system(handle_events.in_set(OnUpdate(AppState::Settings)))
+            .add_system(clear_menu.in_schedule(OnExit(AppState::Settings)));
+    }
+}
+
+const SETTINGS_KEY: &str = "settings";
+
+const VOLUME_STEP: f32 = 0.1;
+
+/// The text scales to cycle through, smallest first.
+pub const TEXT_SCALES: [f32; 4] = [1., 1.25, 1.5, 2.];
+
+/// Player preferences, persisted between sessions.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// The code of the language text is shown in, like "en".
+    pub language: String,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    /// How fast the simulation runs in levels that don't set their own speed.
+    pub simulation_speed: f32,
+    /// Turns off decorative animation, like pulsing highlights.
+    pub reduced_motion: bool,
+    pub high_contrast: bool,
+    pub text_scale: f32,
+    pub plant_palette: PlantPalette,
+    /// Draws a symbol over each plant and marks fertile ground, so neither relies on colour alone.
+    pub tile_glyphs: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: DEFAULT_LANGUAGE.to_string(),
+            master_volume: 1.,
+            music_volume: 1.,
+            sfx_volume: 1.,
+            simulation_speed: DEFAULT_SPEED,
+            reduced_motion: false,
+            high_contrast: false,
+            text_scale: 1.,
+            plant_palette: PlantPalette::Standard,
+            tile_glyphs: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn music_volume(&self) -> f32 {
+        self.master_volume * self.music_volume
+    }
+
+    pub fn sfx_volume(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    pub fn palette(&self) -> Palette {
+        if self.high_contrast {
+            Palette::HIGH_CONTRAST
+        } else {
+            Palette::STANDARD
+        }
+    }
+
+    pub fn save(&self) {
+        storage::save(SETTINGS_KEY, self);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Language,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    SimulationSpeed,
+    ReducedMotion,
+    HighContrast,
+    TextScale,
+    PlantPalette,
+    TileGlyphs,
+}
+
+impl Setting {
+    const ALL: [Setting; 10] = [
+        Setting::Language,
+        Setting::MasterVolume,
+        Setting::MusicVolume,
+        Setting::SfxVolume,
+        Setting::SimulationSpeed,
+        Setting::ReducedMotion,
+        Setting::HighContrast,
+        Setting::TextScale,
+        Setting::PlantPalette,
+        Setting::TileGlyphs,
+    ];
+
+    /// The key of the setting's name in the string tables.
+    fn label_key(&self) -> &'static str {
+        match self {
+            Setting::Language => "settings.language",
+            Setting::MasterVolume => "settings.master_volume",
+            Setting::MusicVolume => "settings.music_volume",
+            Setting::SfxVolume => "settings.sfx_volume",
+            Setting::SimulationSpeed => "settings.simulation_speed",
+            Setting::ReducedMotion => "settings.reduced_motion",
+            Setting::HighContrast => "settings.high_contrast",
+            Setting::TextScale => "settings.text_scale",
+            Setting::PlantPalette => "settings.plant_palette",
+            Setting::TileGlyphs => "settings.tile_glyphs",
+        }
+    }
+
+    fn value(&self, settings: &Settings, strings: &Strings) -> String {
+        let percent = |v: f32| format!("{}%", (v * 100.).round());
+        let toggle = |v: bool| strings.get(if v { "common.on" } else { "common.off" });
+        match self {
+            Setting::Language => strings.language_name(&settings.language),
+            Setting::MasterVolume => percent(settings.master_volume),
+            Setting::MusicVolume => percent(settings.music_volume),
+            Setting::SfxVolume => percent(settings.sfx_volume),
+            Setting::SimulationSpeed => format!("{}x", settings.simulation_speed),
+            Setting::ReducedMotion => toggle(settings.reduced_motion),
+            Setting::HighContrast => toggle(settings.high_contrast),
+            Setting::TextScale => percent(settings.text_scale),
+            Setting::PlantPalette => strings.get(settings.plant_palette.label_key()),
+            Setting::TileGlyphs => toggle(settings.tile_glyphs),
+        }
+    }
+
+    /// Moves the setting up or down a step, or flips it if it is on or off.
+    fn adjust(&self, settings: &mut Settings, strings: &Strings, up: bool) {
+        let volume = |v: &mut f32| {
+            let step = if up { VOLUME_STEP } else { -VOLUME_STEP };
+            *v = ((*v + step) * 10.).round().clamp(0., 10.) / 10.;
+        };
+        match self {
+            Setting::Language => {
+                let languages = strings.languages();
+                let index = languages
+                    .iter()
+                    .position(|code| *code == settings.language)
+                    .unwrap_or_default();
+                let index = if up {
+                    (index + 1) % languages.len()
+                } else {
+                    (index + languages.len() - 1) % languages.len()
+                };
+                settings.language = languages[index].to_string();
+            }
+            Setting::MasterVolume => volume(&mut settings.master_volume),
+            Setting::MusicVolume => volume(&mut settings.music_volume),
+            Setting::SfxVolume => volume(&mut settings.sfx_volume),
+            Setting::SimulationSpeed => {
+                settings.simulation_speed = step_through(&SPEEDS, settings.simulation_speed, up)
+            }
+            Setting::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            Setting::HighContrast => settings.high_contrast = !settings.high_contrast,
+            Setting::TextScale => {
+                settings.text_scale = step_through(&TEXT_SCALES, settings.text_scale, up)
+            }
+            Setting::PlantPalette => {
+                settings.plant_palette = match settings.plant_palette {
+                    PlantPalette::Standard => PlantPalette::ColourBlind,
+                    PlantPalette::ColourBlind => PlantPalette::Standard,
+                }
+            }
+            Setting::TileGlyphs => settings.tile_glyphs = !settings.tile_glyphs,
+        }
+    }
+}
+
+fn step_through(values: &[f32], current: f32, up: bool) -> f32 {
+    let index = values
+        .iter()
+        .position(|v| *v >= current)
+        .unwrap_or(values.len() - 1);
+    let index = if up {
+        (index + 1).min(values.len() - 1)
+    } else {
+        index.saturating_sub(1)
+    };
+    values[index]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsEvent {
+    Adjust(Setting, bool),
+    Reset,
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut palette: ResMut<Palette>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    *palette = settings.palette();
+    clear_color.0 = palette.background;
+}
+
+/// The font sizes a UI text was styled with, and what the text scale made of them.
+#[derive(Component, Debug, Clone, PartialEq)]
+struct ScaledText {
+    base: Vec<f32>,
+    applied: Vec<f32>,
+}
+
+/// Scales the font size of every UI text, leaving the layout around it alone.
+fn scale_text(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut texts: Query<(Entity, &mut Text, Option<&mut ScaledText>), With<Node>>,
+) {
+    for (entity, mut text, scaled) in texts.iter_mut() {
+        let sizes = text
+            .sections
+            .iter()
+            .map(|section| section.style.font_size)
+            .collect::<Vec<_>>();
+        let base = match &scaled {
+            // Still at the scaled sizes, so nothing has restyled the text since it was scaled.
+            Some(scaled) if scaled.applied == sizes => {
+                if !settings.is_changed() {
+                    continue;
+                }
+                scaled.base.clone()
+            }
+            _ => sizes,
+        };
+
+        let applied = base
+            .iter()
+            .map(|size| size * settings.text_scale)
+            .collect::<Vec<_>>();
+        for (section, size) in text.sections.iter_mut().zip(applied.iter()) {
+            section.style.font_size = *size;
+        }
+
+        let update = ScaledText { base, applied };
+        match scaled {
+            Some(mut scaled) => *scaled = update,
+            None => {
+                commands.entity(entity).insert(update);
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct MenuItem;
+
+fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    settings: Res<Settings>,
+    strings: Res<Strings>,
+    query: Query<Entity, With<MenuItem>>,
+) {
+    if !query.is_empty() && !settings.is_changed() && !strings.is_changed() {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    styles.insert(assets.ui_style.clone());
+    let ui = commands.spawn(MenuItem).id();
+
+    let rows = Setting::ALL
+        .iter()
+        .map(|setting| {
+            (
+                *setting,
+                strings.get(setting.label_key()),
+                setting.value(&settings, &strings),
+            )
+        })
+        .collect::<Vec<_>>();
+    let title = strings.get("settings.title");
+    let reset = strings.get("common.reset");
+    let menu = strings.get("common.menu");
+
+    let root = strings.root_class("root");
+    commands.add(eml! {
+        <body {ui} class={root}>
+            <div c:header>{title}</div>
+            <for row in=rows>
+                <div c:controls_row>
+                    <span c:controls_action>{row.1.clone()}</span>
+                    <button c:exit_button on:press=move |ctx| ctx.send_event(SettingsEvent::Adjust(row.0, false))>
+                        <span c:content>"<"</span>
+                    </button>
+                    <span c:controls_bindings c:setting_value>{row.2.clone()}</span>
+                    <button c:exit_button on:press=move |ctx| ctx.send_event(SettingsEvent::Adjust(row.0, true))>
+                        <span c:content>">"</span>
+                    </button>
+                </div>
+            </for>
+            <button c:menu_button c:small_menu_button c:secondary on:press=|ctx| ctx.send_event(SettingsEvent::Reset)>
+                <span c:content>
+                {reset}
+                </span>
+            </button>
+            <button c:menu_button c:small_menu_button on:press=|ctx| ctx.commands().insert_resource(NextState(Some(AppState::Menu)))>
+                <span c:content>
+                {menu}
+                </span>
+            </button>
+        </body>
+    });
+}
+
+fn handle_events(
+    mut events: EventReader<SettingsEvent>,
+    mut settings: ResMut<Settings>,
+    strings: Res<Strings>,
+) {
+    for event in events.iter() {
+        match event {
+            SettingsEvent::Adjust(setting, up) => setting.adjust(&mut settings, &strings, *up),
+            // The language is kept, so the menu stays readable after a reset.
+            SettingsEvent::Reset => {
+                *settings = Settings {
+                    language: settings.language.clone(),
+                    ..default()
+                }
+            }
+        }
+        settings.save();
+    }
+}
+
+fn clear_menu(mut commands: Commands, query: Query<Entity, With<MenuItem>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
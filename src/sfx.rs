@@ -0,0 +1,136 @@
+//! Sound effects. Gameplay events are turned into [`PlaySfx`] events, and a single system plays them,
+//! so anything can trigger a sound without knowing about the audio setup.
+//! Without an audio output, like when running headless, the sounds are quietly dropped.
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    assets::GameAssets,
+    control::{GainPower, Power, UsePower},
+    hazards::PlayerEvent,
+    settings::Settings,
+    target::{LevelCompleted, TargetCompleted},
+    update_tiles::{FireIgnited, PlantSpread},
+};
+
+pub struct SfxPlugin;
+
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>()
+            .add_system(power_sfx)
+            .add_system(simulation_sfx)
+            .add_system(target_sfx)
+            .add_system(hazard_sfx)
+            .add_system(
+                play_sfx
+                    .after(power_sfx)
+                    .after(simulation_sfx)
+                    .after(target_sfx)
+                    .after(hazard_sfx),
+            );
+    }
+}
+
+pub const SFX_VOLUME: f32 = 0.6;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    UsePower(Power),
+    GainPower,
+    Spread,
+    Ignite,
+    Target,
+    LevelComplete,
+    Hurt,
+}
+
+impl Sfx {
+    fn source(&self, assets: &GameAssets) -> Handle<AudioSource> {
+        match self {
+            Sfx::UsePower(Power::Fertilize) => &assets.sfx_fertilize,
+            Sfx::UsePower(Power::Fire) => &assets.sfx_fire,
+            Sfx::UsePower(Power::Seed) => &assets.sfx_seed,
+            Sfx::UsePower(Power::Drain) => &assets.sfx_drain,
+            Sfx::UsePower(Power::Plant) => &assets.sfx_plant,
+            Sfx::GainPower => &assets.sfx_gain_power,
+            Sfx::Spread => &assets.sfx_spread,
+            Sfx::Ignite => &assets.sfx_ignite,
+            Sfx::Target => &assets.sfx_target,
+            Sfx::LevelComplete => &assets.sfx_level_complete,
+            Sfx::Hurt => &assets.sfx_hurt,
+        }
+        .clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlaySfx(pub Sfx);
+
+fn power_sfx(
+    mut use_power: EventReader<UsePower>,
+    mut gain_power: EventReader<GainPower>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for UsePower(power, _) in use_power.iter() {
+        sfx.send(PlaySfx(Sfx::UsePower(power.clone())));
+    }
+    if gain_power.iter().count() > 0 {
+        sfx.send(PlaySfx(Sfx::GainPower));
+    }
+}
+
+fn simulation_sfx(
+    mut spread: EventReader<PlantSpread>,
+    mut ignited: EventReader<FireIgnited>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    // A single generation can change many tiles, but only needs one of each sound.
+    if spread.iter().count() > 0 {
+        sfx.send(PlaySfx(Sfx::Spread));
+    }
+    if ignited.iter().count() > 0 {
+        sfx.send(PlaySfx(Sfx::Ignite));
+    }
+}
+
+fn target_sfx(
+    mut target_completed: EventReader<TargetCompleted>,
+    mut level_completed: EventReader<LevelCompleted>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    // Finishing the level has its own sound, rather than the one for the target that finished it.
+    let targets = target_completed.iter().count();
+    if level_completed.iter().count() > 0 {
+        sfx.send(PlaySfx(Sfx::LevelComplete));
+    } else if targets > 0 {
+        sfx.send(PlaySfx(Sfx::Target));
+    }
+}
+
+fn hazard_sfx(mut events: EventReader<PlayerEvent>, mut sfx: EventWriter<PlaySfx>) {
+    for event in events.iter() {
+        // The burn that kills the player is left to the failure screen.
+        if matches!(event, PlayerEvent::Burned { health } if *health > 0) {
+            sfx.send(PlaySfx(Sfx::Hurt));
+        }
+    }
+}
+
+fn play_sfx(
+    mut events: EventReader<PlaySfx>,
+    audio: Option<Res<Audio>>,
+    assets: Option<Res<GameAssets>>,
+    settings: Res<Settings>,
+) {
+    let mut played = HashSet::default();
+    for PlaySfx(sfx) in events.iter() {
+        let (Some(audio), Some(assets)) = (audio.as_ref(), assets.as_ref()) else { continue; };
+        if played.insert(sfx.clone()) {
+            audio.play_with_settings(
+                sfx.source(&assets),
+                PlaybackSettings::ONCE.with_volume(SFX_VOLUME * settings.sfx_volume()),
+            );
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! A breadth first search over everything the player can do - moving, using a power,
+//! or waiting for the next generation - to find the shortest way to complete a level.
+//! Anything that would burn or drown the player is treated as a dead end, so a solution never
+//! walks the player into harm.
+
+use std::collections::VecDeque;
+
+use bevy::utils::{HashMap, HashSet};
+
+use crate::{
+    control::{Action, Power},
+    hazards::PlayerRules,
+    history::LevelSnapshot,
+    movement::{Abilities, Ability, DIRECTIONS},
+    target::{Reward, Target},
+    tile::{Ground, Plant, PlantDefinitions, Tile},
+    update_tiles::{power_effect, update_tile},
+};
+
+/// How many states the search visits before giving up.
+const MAX_SEARCHED_STATES: usize = 20_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolverAction {
+    Move(Action, Tile),
+    UsePower(Power, Tile),
+    Wait,
+}
+
+/// Everything fixed about a level while it is being solved.
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub targets: Vec<Target>,
+    pub plants: PlantDefinitions,
+    pub rules: PlayerRules,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SearchState {
+    tiles: Vec<(Tile, Ground, Plant)>,
+    player: Tile,
+    powers: Vec<(Power, usize)>,
+    seed: Option<String>,
+    abilities: Vec<Ability>,
+    used_targets: Vec<Tile>,
+}
+
+impl SearchState {
+    fn from_snapshot(snapshot: &LevelSnapshot) -> Self {
+        let mut state = Self {
+            tiles: snapshot.tiles.clone(),
+            player: snapshot.player,
+            powers: snapshot.powers.clone(),
+            seed: snapshot.seed.0.as_ref().map(|(id, _)| id.clone()),
+            abilities: snapshot.abilities.clone(),
+            used_targets: snapshot.used_targets.clone(),
+        };
+        state.normalize();
+        state
+    }
+
+    /// Keeps equal states hashing the same, whatever order things were added in.
+    fn normalize(&mut self) {
+        self.tiles.sort_by_key(|(t, _, _)| (t.0, t.1));
+        self.powers.retain(|(_, count)| *count > 0);
+        self.powers.sort();
+        self.abilities.sort();
+        self.abilities.dedup();
+        self.used_targets.sort_by_key(|t| (t.0, t.1));
+    }
+
+    fn tile_map(&self) -> HashMap<Tile, (&Ground, &Plant)> {
+        self.tiles.iter().map(|(t, g, p)| (*t, (g, p))).collect()
+    }
+
+    fn adjust_power(&mut self, power: Power, amount: i32) {
+        match self.powers.iter_mut().find(|(p, _)| *p == power) {
+            Some((_, count)) => *count = (*count as i32 + amount).max(0) as usize,
+            None if amount > 0 => self.powers.push((power, amount as usize)),
+            None => {}
+        }
+    }
+
+    /// Whether the player would be hurt where they stand - burning, or drowning without Swim.
+    fn hurts_player(&self, puzzle: &Puzzle) -> bool {
+        let standing_on = self.tiles.iter().find(|(t, _, _)| *t == self.player);
+        let Some((_, ground, plant)) = standing_on else { return false; };
+        match plant {
+            Plant::Fire(_) => puzzle.rules.fire_damage > 0,
+            Plant::Empty => {
+                puzzle.rules.drown
+                    && *ground == Ground::Water
+                    && !self.abilities.contains(&Ability::Swim)
+            }
+            _ => false,
+        }
+    }
+
+    /// Claims any target under the player, returning true if that completes the level.
+    fn claim_targets(&mut self, puzzle: &Puzzle) -> bool {
+        let standing_on = self.tiles.iter().find(|(t, _, _)| *t == self.player);
+        let Some((_, _, Plant::Plant(plant))) = standing_on else { return false; };
+        let plant = plant.clone();
+
+        let mut completed = false;
+        for target in puzzle.targets.iter() {
+            if target.0 != self.player || target.1 != plant || self.used_targets.contains(&target.0)
+            {
+                continue;
+            }
+            self.used_targets.push(target.0);
+            completed |= target.2 == Reward::CompleteLevel;
+            if let Some(power) = target.2.power() {
+                self.adjust_power(power, 1);
+            }
+            if let Some(ability) = target.2.ability() {
+                self.abilities.push(ability);
+            }
+        }
+        completed
+    }
+
+    fn successors(&self, puzzle: &Puzzle) -> Vec<(SolverAction, SearchState)> {
+        let tiles = self.tile_map();
+        let abilities = Abilities(self.abilities.iter().copied().collect());
+        let mut next = vec![];
+
+        for direction in DIRECTIONS {
+            let Some(to) = abilities.step(self.player, direction, &tiles, &puzzle.rules) else { continue; };
+            let action = match direction {
+                (0, 1) => Action::Up,
+                (0, -1) => Action::Down,
+                (-1, 0) => Action::Left,
+                _ => Action::Right,
+            };
+            let mut state = self.clone();
+            state.player = to;
+            next.push((SolverAction::Move(action, to), state));
+        }
+
+        for (power, _) in self.powers.iter() {
+            let range = power.range();
+            for target in self.tiles.iter().map(|(t, _, _)| *t) {
+                let distance = target.0.abs_diff(self.player.0).max(target.1.abs_diff(self.player.1));
+                if distance > range {
+                    continue;
+                }
+                let seed = self.seed.as_deref();
+                let Some(effect) = power_effect(power, &target, &tiles, seed, &puzzle.plants) else { continue; };
+
+                let mut state = self.clone();
+                state.adjust_power(power.clone(), -1);
+                if let Some(gained) = effect.gained {
+                    state.adjust_power(gained, 1);
+                }
+                if let Some(seed) = effect.seed {
+                    state.seed = seed;
+                }
+                for (tile, ground, plant) in effect.tiles {
+                    if let Some(entry) = state.tiles.iter_mut().find(|(t, _, _)| *t == tile) {
+                        *entry = (tile, ground, plant);
+                    }
+                }
+                next.push((SolverAction::UsePower(power.clone(), target), state));
+            }
+        }
+
+        let mut state = self.clone();
+        state.tiles = self
+            .tiles
+            .iter()
+            .map(|(tile, ground, plant)| {
+                let (ground, plant) = update_tile(ground, plant, tile, &tiles, &puzzle.plants);
+                (*tile, ground, plant)
+            })
+            .collect();
+        next.push((SolverAction::Wait, state));
+
+        next
+    }
+}
+
+/// How far a [`Search`] has got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchResult {
+    Searching,
+    /// The first action on the shortest path to completing the level.
+    Found(SolverAction),
+    /// Every reachable state has been tried, or too many to keep going.
+    NotFound,
+}
+
+/// A breadth first search that can be run a few states at a time, so it never holds up a frame.
+pub struct Search {
+    puzzle: Puzzle,
+    visited: HashSet<SearchState>,
+    queue: VecDeque<(SearchState, Option<SolverAction>)>,
+}
+
+impl Search {
+    pub fn new(snapshot: &LevelSnapshot, puzzle: Puzzle) -> Self {
+        let start = SearchState::from_snapshot(snapshot);
+        let mut visited = HashSet::default();
+        visited.insert(start.clone());
+        Self {
+            puzzle,
+            visited,
+            queue: VecDeque::from([(start, None)]),
+        }
+    }
+
+    /// Looks at up to `budget` more states.
+    pub fn advance(&mut self, budget: usize) -> SearchResult {
+        for _ in 0..budget {
+            let Some((state, first)) = self.queue.pop_front() else { return SearchResult::NotFound; };
+            for (action, mut next) in state.successors(&self.puzzle) {
+                if next.hurts_player(&self.puzzle) {
+                    continue;
+                }
+                let completed = next.claim_targets(&self.puzzle);
+                let first = first.clone().unwrap_or(action);
+                if completed {
+                    return SearchResult::Found(first);
+                }
+
+                next.normalize();
+                if self.visited.len() >= MAX_SEARCHED_STATES || !self.visited.insert(next.clone()) {
+                    continue;
+                }
+                self.queue.push_back((next, Some(first)));
+            }
+        }
+
+        if self.queue.is_empty() {
+            SearchResult::NotFound
+        } else {
+            SearchResult::Searching
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::{ecs::system::SystemState, prelude::*};
+
+    use super::*;
+    use crate::{
+        headless::HeadlessPlugin, history::LevelState, level_asset::CurrentLevel, states::AppState,
+    };
+
+    /// Loads a shipped level in a headless app and captures it the way a hint request does.
+    fn load_level(file: &str) -> (LevelSnapshot, Puzzle) {
+        let mut app = App::new();
+        app.add_plugin(HeadlessPlugin);
+
+        let started = Instant::now();
+        let mut requested = false;
+        while started.elapsed() < Duration::from_secs(60) {
+            app.update();
+            match app.world.resource::<State<AppState>>().0 {
+                AppState::Menu if !requested => {
+                    let level = app
+                        .world
+                        .resource::<AssetServer>()
+                        .load(format!("levels/{file}"));
+                    app.world.insert_resource(CurrentLevel(Some(level)));
+                    app.world
+                        .insert_resource(NextState(Some(AppState::LoadingLevel)));
+                    requested = true;
+                }
+                AppState::InGame => {
+                    let mut state: SystemState<(
+                        LevelState,
+                        Query<&Target>,
+                        Res<PlantDefinitions>,
+                        Res<PlayerRules>,
+                    )> = SystemState::new(&mut app.world);
+                    let (level, targets, plants, rules) = state.get(&app.world);
+                    let targets = targets.iter().cloned().collect::<Vec<_>>();
+                    if let (Some(snapshot), false) = (level.snapshot(), targets.is_empty()) {
+                        let puzzle = Puzzle {
+                            targets,
+                            plants: plants.clone(),
+                            rules: rules.clone(),
+                        };
+                        return (snapshot, puzzle);
+                    }
+                }
+                _ => {}
+            }
+        }
+        panic!("{file} didn't load");
+    }
+
+    #[test]
+    fn solves_a_shipped_level() {
+        let (snapshot, puzzle) = load_level("a_call.lvl.yaml");
+        assert!(matches!(
+            Search::new(&snapshot, puzzle).advance(usize::MAX),
+            SearchResult::Found(_)
+        ));
+    }
+}
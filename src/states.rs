@@ -9,5 +9,10 @@ pub enum AppState {
     LoadingLevel,
     InGame,
     LevelComplete,
+    LevelFailed,
     Credits,
+    Controls,
+    Settings,
+    Almanac,
+    Editor,
 }
@@ -0,0 +1,78 @@
+//! Small key/value persistence for settings and progress.
+//! Values are stored as JSON - in `localStorage` on the web, and as files in a `saves` folder otherwise.
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let value = read(key)?;
+    match serde_json::from_str(&value) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("Couldn't parse stored {key}: {e}");
+            None
+        }
+    }
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(value) => write(key, &value),
+        Err(e) => warn!("Couldn't serialize {key}: {e}"),
+    }
+}
+
+pub fn remove(key: &str) {
+    delete(key);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_FOLDER: &str = "saves";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> std::path::PathBuf {
+    std::path::Path::new(SAVE_FOLDER).join(format!("{key}.json"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, value: &str) {
+    if let Err(e) =
+        std::fs::create_dir_all(SAVE_FOLDER).and_then(|_| std::fs::write(path(key), value))
+    {
+        warn!("Couldn't save {key}: {e}");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn delete(key: &str) {
+    let _ = std::fs::remove_file(path(key));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, value: &str) {
+    let Some(storage) = local_storage() else { return; };
+    if storage.set_item(key, value).is_err() {
+        warn!("Couldn't save {key}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn delete(key: &str) {
+    let Some(storage) = local_storage() else { return; };
+    let _ = storage.remove_item(key);
+}
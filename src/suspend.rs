@@ -0,0 +1,180 @@
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hint::Hints,
+    history::{LevelSnapshot, LevelState, PendingRestore, UndoHistory},
+    level_asset::{level_file_name, CurrentLevel},
+    progress::LevelStats,
+    states::AppState,
+    storage,
+    target::LevelCompleted,
+};
+
+pub struct SuspendPlugin;
+
+impl Plugin for SuspendPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Suspended(storage::load(SUSPENDED_KEY)))
+            .add_event::<SuspendLevel>()
+            .add_event::<ResumeLevel>()
+            .add_system(suspend_level.in_set(OnUpdate(AppState::InGame)))
+            .add_system(suspend_on_leave.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                suspend_on_quit
+                    .in_base_set(CoreSet::Last)
+                    .run_if(in_state(AppState::InGame).and_then(on_event::<AppExit>())),
+            )
+            .add_system(resume_level.in_set(OnUpdate(AppState::Menu)))
+            .add_system(clear_suspended.run_if(on_event::<LevelCompleted>()))
+            .add_system(clear_suspended.in_schedule(OnEnter(AppState::LevelFailed)));
+    }
+}
+
+const SUSPENDED_KEY: &str = "suspended";
+
+/// An in-progress level saved when the player left it part way through.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspendedLevel {
+    pub level: String,
+    pub snapshot: LevelSnapshot,
+    #[serde(default)]
+    pub stats: LevelStats,
+    /// Hints left to take, or `None` for saves made before hints were kept.
+    #[serde(default)]
+    pub hints: Option<usize>,
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Suspended(pub Option<SuspendedLevel>);
+
+/// Saves the current level and returns to the menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuspendLevel;
+
+/// Reloads the suspended level and puts it back the way it was left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResumeLevel;
+
+fn suspend_level(mut events: EventReader<SuspendLevel>, mut commands: Commands) {
+    if events.iter().last().is_none() {
+        return;
+    }
+    // The level is saved as it's left, in `suspend_on_leave`.
+    commands.insert_resource(NextState(Some(AppState::Menu)));
+}
+
+/// Leaving a level any way other than finishing, failing or reloading it keeps it to come back to.
+fn suspend_on_leave(
+    state: Res<State<AppState>>,
+    level_state: LevelState,
+    stats: Res<LevelStats>,
+    hints: Res<Hints>,
+    current_level: Res<CurrentLevel>,
+    server: Res<AssetServer>,
+    mut suspended: ResMut<Suspended>,
+) {
+    if matches!(
+        state.0,
+        AppState::LevelComplete | AppState::LevelFailed | AppState::LoadingLevel
+    ) {
+        return;
+    }
+    save_suspended(
+        &level_state,
+        &stats,
+        &hints,
+        &current_level,
+        &server,
+        &mut suspended,
+    );
+}
+
+/// Closing the game mid level keeps it too.
+fn suspend_on_quit(
+    level_state: LevelState,
+    stats: Res<LevelStats>,
+    hints: Res<Hints>,
+    current_level: Res<CurrentLevel>,
+    server: Res<AssetServer>,
+    mut suspended: ResMut<Suspended>,
+) {
+    save_suspended(
+        &level_state,
+        &stats,
+        &hints,
+        &current_level,
+        &server,
+        &mut suspended,
+    );
+}
+
+fn save_suspended(
+    state: &LevelState,
+    stats: &LevelStats,
+    hints: &Hints,
+    current_level: &CurrentLevel,
+    server: &AssetServer,
+    suspended: &mut Suspended,
+) {
+    // Levels that weren't loaded from a file, like editor play tests, can't be resumed.
+    let level = current_level
+        .0
+        .as_ref()
+        .and_then(|level| level_file_name(server, level));
+    let (Some(level), Some(snapshot)) = (level, state.snapshot()) else { return; };
+
+    let level = SuspendedLevel {
+        level,
+        snapshot,
+        stats: stats.clone(),
+        hints: Some(hints.remaining),
+    };
+    storage::save(SUSPENDED_KEY, &level);
+    suspended.0 = Some(level);
+}
+
+fn resume_level(
+    mut events: EventReader<ResumeLevel>,
+    mut suspended: ResMut<Suspended>,
+    server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    if events.iter().last().is_none() {
+        return;
+    }
+    let Some(suspended) = suspended.0.take() else { return; };
+    storage::remove(SUSPENDED_KEY);
+
+    let url = format!("levels/{}", suspended.level);
+    commands.insert_resource(CurrentLevel(Some(server.load(url))));
+    commands.insert_resource(PendingRestore(Some(suspended.snapshot)));
+    // Undo steps from whatever was played before don't belong to this level.
+    commands.insert_resource(UndoHistory::default());
+    commands.insert_resource(suspended.stats);
+    if let Some(remaining) = suspended.hints {
+        commands.insert_resource(Hints {
+            remaining,
+            ..default()
+        });
+    }
+    commands.insert_resource(NextState(Some(AppState::LoadingLevel)));
+}
+
+/// Finishing or failing the suspended level drops it, so the menu doesn't offer it back.
+/// Other levels, and editor play tests, leave it alone.
+fn clear_suspended(
+    mut suspended: ResMut<Suspended>,
+    current_level: Res<CurrentLevel>,
+    server: Res<AssetServer>,
+) {
+    let current = current_level
+        .0
+        .as_ref()
+        .and_then(|level| level_file_name(&server, level));
+    let Some(current) = current else { return; };
+    if suspended.0.as_ref().map(|level| &level.level) == Some(&current) {
+        suspended.0 = None;
+        storage::remove(SUSPENDED_KEY);
+    }
+}
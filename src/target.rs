@@ -1,19 +1,30 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, sprite::Anchor};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     assets::GameAssets,
     control::{GainPower, Player, Power},
+    locale::Strings,
+    movement::{Ability, GainAbility},
+    settings::Settings,
     states::AppState,
     tile::{Ground, Plant, PlantDefinitions, Tile, TileAsset, TILE_WORLD_SIZE},
+    update_tiles::update_tiles,
 };
 
 pub struct TargetPlugin;
 
 impl Plugin for TargetPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(setup_target.in_set(OnUpdate(AppState::InGame)))
-            .add_system(process_target.in_set(OnUpdate(AppState::InGame)));
+        app.add_event::<LevelCompleted>()
+            .add_event::<TargetCompleted>()
+            .add_system(setup_target.in_set(OnUpdate(AppState::InGame)))
+            .add_system(update_shrine_labels.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                process_target
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(update_tiles),
+            );
     }
 }
 
@@ -24,19 +35,85 @@ pub enum Reward {
     Burn,
     Seed,
     Drain,
+    Swim,
+    Float,
+    Climb,
 }
 
+impl Reward {
+    pub fn image(&self, assets: &GameAssets) -> Handle<Image> {
+        match self {
+            Reward::CompleteLevel => &assets.goal,
+            Reward::Fertilize => &assets.shrine_fertilize,
+            Reward::Burn => &assets.shrine_fire,
+            Reward::Seed => &assets.shrine_seed,
+            Reward::Drain => &assets.shrine_drain,
+            Reward::Swim => &assets.shrine_swim,
+            Reward::Float => &assets.shrine_float,
+            Reward::Climb => &assets.shrine_climb,
+        }
+        .clone()
+    }
+
+    pub fn power(&self) -> Option<Power> {
+        match self {
+            Reward::Fertilize => Some(Power::Fertilize),
+            Reward::Burn => Some(Power::Fire),
+            Reward::Seed => Some(Power::Seed),
+            Reward::Drain => Some(Power::Drain),
+            _ => None,
+        }
+    }
+
+    pub fn ability(&self) -> Option<Ability> {
+        match self {
+            Reward::Swim => Some(Ability::Swim),
+            Reward::Float => Some(Ability::Float),
+            Reward::Climb => Some(Ability::Climb),
+            _ => None,
+        }
+    }
+
+    /// What claiming the reward does, to finish a sentence like "grow moss here to ...".
+    pub fn describe(&self, strings: &Strings) -> String {
+        if let Some(power) = self.power() {
+            return strings.format("reward.gain", &[("power", &strings.get(power.label_key()))]);
+        }
+        strings.get(match self.ability() {
+            Some(Ability::Swim) => "reward.swim",
+            Some(Ability::Float) => "reward.float",
+            Some(Ability::Climb) => "reward.climb",
+            None => "reward.complete",
+        })
+    }
+}
+
+const SHRINE_LABEL_SIZE: f32 = 14.;
+
 #[derive(Component, Debug, Clone)]
 pub struct Target(pub Tile, pub String, pub Reward);
 
 #[derive(Component)]
 pub struct UsedTarget;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LevelCompleted;
+
+/// Sent when the player claims a target, before its reward is handed out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TargetCompleted(pub Tile, pub Reward);
+
+/// The name of the plant a shrine wants, drawn under it.
+#[derive(Component, Debug, Clone)]
+struct ShrineLabel(String);
+
 fn setup_target(
     mut commands: Commands,
     targets: Query<(Entity, &Target), Without<Sprite>>,
     assets: Res<GameAssets>,
     plants: Res<PlantDefinitions>,
+    settings: Res<Settings>,
+    strings: Res<Strings>,
 ) {
     for (e, target) in targets.iter() {
         commands
@@ -49,21 +126,33 @@ fn setup_target(
                     custom_size: Some(Vec2::new(TILE_WORLD_SIZE, TILE_WORLD_SIZE)),
                     ..default()
                 },
-                texture: match target.2 {
-                    Reward::CompleteLevel => &assets.goal,
-                    Reward::Fertilize => &assets.shrine_fertilize,
-                    Reward::Burn => &assets.shrine_fire,
-                    Reward::Seed => &assets.shrine_seed,
-                    Reward::Drain => &assets.shrine_drain,
-                }
-                .clone(),
+                texture: target.2.image(&assets),
                 ..default()
             })
             .with_children(|p| {
+                // The plant's name sits under the shrine, so the goal can be read without its colour.
+                p.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        plants.name(&target.1),
+                        TextStyle {
+                            font: assets.font_for(&strings),
+                            font_size: SHRINE_LABEL_SIZE,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    text_anchor: Anchor::TopCenter,
+                    transform: Transform::from_translation(Vec3::new(
+                        0.,
+                        -TILE_WORLD_SIZE * 0.2,
+                        2.,
+                    )),
+                    ..default()
+                })
+                .insert(ShrineLabel(target.1.clone()));
                 let Some((image, color)) = plants
                     .assets
                     .get(target.1.as_str())
-                    .map(|TileAsset(asset, c)| (asset.clone(), *c)) else { return; };
+                    .map(|TileAsset(asset, _)| (asset.clone(), plants.color(&target.1, settings.plant_palette))) else { return; };
                 p.spawn(SpriteBundle {
                     sprite: Sprite {
                         color,
@@ -78,12 +167,33 @@ fn setup_target(
     }
 }
 
+/// Renames the labels when the language changes, switching font if its letters need it.
+fn update_shrine_labels(
+    mut labels: Query<(&ShrineLabel, &mut Text)>,
+    assets: Res<GameAssets>,
+    plants: Res<PlantDefinitions>,
+    strings: Res<Strings>,
+) {
+    if !strings.is_changed() && !plants.is_changed() {
+        return;
+    }
+    for (label, mut text) in labels.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.value = plants.name(&label.0);
+            section.style.font = assets.font_for(&strings);
+        }
+    }
+}
+
 fn process_target(
     players: Query<&Player>,
     targets: Query<(Entity, &Target), Without<UsedTarget>>,
     tiles: Query<(&Tile, &Ground, &Plant)>,
     mut commands: Commands,
     mut gain_power: EventWriter<GainPower>,
+    mut gain_ability: EventWriter<GainAbility>,
+    mut level_completed: EventWriter<LevelCompleted>,
+    mut target_completed: EventWriter<TargetCompleted>,
 ) {
     let Ok(player) = players.get_single() else { return; };
     for (tile, _, plant) in tiles.iter() {
@@ -93,15 +203,17 @@ fn process_target(
                     let Plant::Plant(p) = plant else { continue; };
                     if p.as_str() == target.1 {
                         commands.entity(e).insert(UsedTarget).despawn_descendants();
-                        match target.2 {
-                            Reward::CompleteLevel => {
-                                commands.insert_resource(NextState(Some(AppState::LevelComplete)));
-                            }
-                            Reward::Fertilize => gain_power.send(GainPower(Power::Fertilize)),
-                            Reward::Burn => gain_power.send(GainPower(Power::Fire)),
-                            Reward::Seed => gain_power.send(GainPower(Power::Seed)),
-                            Reward::Drain => gain_power.send(GainPower(Power::Drain)),
-                        };
+                        target_completed.send(TargetCompleted(target.0, target.2));
+                        if target.2 == Reward::CompleteLevel {
+                            level_completed.send(LevelCompleted);
+                            commands.insert_resource(NextState(Some(AppState::LevelComplete)));
+                        }
+                        if let Some(power) = target.2.power() {
+                            gain_power.send(GainPower(power));
+                        }
+                        if let Some(ability) = target.2.ability() {
+                            gain_ability.send(GainAbility(ability));
+                        }
                     }
                 }
             }
@@ -1,4 +1,4 @@
-use std::{fmt, marker::PhantomData, str::FromStr};
+use std::{collections::BTreeMap, fmt, marker::PhantomData, str::FromStr};
 
 use bevy::{
     prelude::*,
@@ -12,7 +12,12 @@ use serde::{
     Deserialize, Deserializer, Serialize,
 };
 
-use crate::{assets::GameAssets, target::Reward};
+use crate::{
+    assets::GameAssets,
+    colors::{PlantPalette, OKABE_ITO},
+    locale::{Strings, DEFAULT_LANGUAGE},
+    target::Reward,
+};
 
 #[derive(
     Component,
@@ -54,10 +59,39 @@ impl FromStr for Ground {
     }
 }
 
+impl Ground {
+    /// The ground as it is with a plant on it - plants leave soil, sand and rock fertile.
+    pub fn under(self, plant: &Plant) -> Self {
+        if !matches!(plant, Plant::Plant(_)) {
+            return self;
+        }
+        match self {
+            Ground::Soil(_) => Ground::Soil(true),
+            Ground::Sand(_) => Ground::Sand(true),
+            Ground::Rock(_) => Ground::Rock(true),
+            _ => self,
+        }
+    }
+
+    /// The key of the name the ground is shown with in the editor and tile tooltips.
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Ground::Empty => "ground.void",
+            Ground::Water => "ground.water",
+            Ground::Soil(false) => "ground.soil",
+            Ground::Soil(true) => "ground.fertile_soil",
+            Ground::Sand(false) => "ground.sand",
+            Ground::Sand(true) => "ground.fertile_sand",
+            Ground::Rock(false) => "ground.rock",
+            Ground::Rock(true) => "ground.fertile_rock",
+        }
+    }
+}
+
 impl ToString for Ground {
     fn to_string(&self) -> String {
         match self {
-            Ground::Empty => "",
+            Ground::Empty => "-",
             Ground::Water => "w",
             Ground::Soil(true) => "gf",
             Ground::Sand(true) => "sf",
@@ -104,12 +138,25 @@ impl FromStr for Plant {
             Ok(Plant::Plant(s.to_string()))
         } else if s == "f" {
             Ok(Plant::Fire(FIRE_DURATION))
+        } else if let Some(Ok(remaining)) = s.strip_prefix('f').map(|n| n.parse()) {
+            Ok(Plant::Fire(remaining))
         } else {
             Ok(Plant::Empty)
         }
     }
 }
 
+impl ToString for Plant {
+    fn to_string(&self) -> String {
+        match self {
+            Plant::Empty => String::new(),
+            Plant::Plant(id) => format!(".{id}"),
+            Plant::Fire(FIRE_DURATION) => "f".to_string(),
+            Plant::Fire(remaining) => format!("f{remaining}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum GameEntity {
     Player,
@@ -137,12 +184,42 @@ impl FromStr for GameEntity {
         } else if s.starts_with("s.") {
             let s = s.trim_start_matches("s.");
             Ok(GameEntity::Target(s.to_string(), Reward::Seed))
+        } else if s.starts_with("w.") {
+            let s = s.trim_start_matches("w.");
+            Ok(GameEntity::Target(s.to_string(), Reward::Swim))
+        } else if s.starts_with("a.") {
+            let s = s.trim_start_matches("a.");
+            Ok(GameEntity::Target(s.to_string(), Reward::Float))
+        } else if s.starts_with("c.") {
+            let s = s.trim_start_matches("c.");
+            Ok(GameEntity::Target(s.to_string(), Reward::Climb))
         } else {
             Err(anyhow::Error::msg("No Entity"))
         }
     }
 }
 
+impl ToString for GameEntity {
+    fn to_string(&self) -> String {
+        match self {
+            GameEntity::Player => "p".to_string(),
+            GameEntity::Target(plant, reward) => {
+                let prefix = match reward {
+                    Reward::CompleteLevel => "t",
+                    Reward::Fertilize => "f",
+                    Reward::Drain => "d",
+                    Reward::Burn => "b",
+                    Reward::Seed => "s",
+                    Reward::Swim => "w",
+                    Reward::Float => "a",
+                    Reward::Climb => "c",
+                };
+                format!("{prefix}.{plant}")
+            }
+        }
+    }
+}
+
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Hash, Serialize, Deserialize)]
 pub struct Tile(pub i8, pub i8);
 
@@ -171,9 +248,43 @@ impl Default for SpreadType {
     }
 }
 
+impl SpreadType {
+    /// How the plant spreads, written out for the almanac.
+    pub fn describe(&self, plants: &PlantDefinitions, strings: &Strings) -> String {
+        let kind = |n: &usize| match n {
+            1 => strings.get("spread.one_of_kind"),
+            n => strings.format("spread.many_of_kind", &[("count", n)]),
+        };
+        match self {
+            SpreadType::AdjacentEmpty(n) => {
+                strings.format("spread.adjacent_empty", &[("kind", &kind(n))])
+            }
+            SpreadType::AdjacentAggresive(n) => {
+                strings.format("spread.adjacent_aggressive", &[("kind", &kind(n))])
+            }
+            SpreadType::AdjacentRequire(n, req) => strings.format(
+                "spread.adjacent_require",
+                &[("plants", &plants.names(req, strings)), ("kind", &kind(n))],
+            ),
+            SpreadType::Seeded => strings.get("spread.seeded"),
+            SpreadType::SeededRequire(req) => strings.format(
+                "spread.seeded_require",
+                &[("plants", &plants.names(req, strings))],
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Reflect, FromReflect, PartialEq, Serialize, Deserialize)]
 pub struct GroundList(pub Vec<Ground>);
 
+impl GroundList {
+    /// The names of the grounds, as a list to read out.
+    pub fn names(&self, strings: &Strings) -> String {
+        strings.list_or(self.0.iter().map(|ground| strings.get(ground.label_key())))
+    }
+}
+
 impl FromStr for GroundList {
     type Err = anyhow::Error;
 
@@ -216,6 +327,79 @@ pub struct PlantDefinition {
     pub required_neighbour_grounds: GroundList,
     #[serde(default)]
     pub required_neighbour_plants: Vec<String>,
+    /// The symbol drawn over the plant when glyphs are turned on, instead of the first letter of its id.
+    #[serde(default)]
+    pub glyph: Option<String>,
+    /// The colour used by the colour blind palette, instead of one picked from it automatically.
+    #[serde(default)]
+    pub accessible_color: Option<Color>,
+    /// The name shown to players, instead of one made from the id.
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Short labels for the almanac, like "ground cover" or "weed".
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The name, description and tags in other languages, by language code.
+    #[serde(default)]
+    #[reflect(ignore)]
+    pub translations: BTreeMap<String, PlantText>,
+}
+
+/// A plant's text in one language. Anything left out is shown in English.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlantText {
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl PlantDefinition {
+    /// The name shown to players in English.
+    pub fn name(&self) -> String {
+        self.display_name
+            .clone()
+            .unwrap_or_else(|| name_from_id(&self.id))
+    }
+
+    /// The name shown to players in the given language.
+    pub fn name_in(&self, language: &str) -> String {
+        self.translations
+            .get(language)
+            .and_then(|text| text.display_name.clone())
+            .unwrap_or_else(|| self.name())
+    }
+
+    pub fn description_in(&self, language: &str) -> Option<String> {
+        self.translations
+            .get(language)
+            .and_then(|text| text.description.clone())
+            .or_else(|| self.description.clone())
+    }
+
+    pub fn tags_in(&self, language: &str) -> Vec<String> {
+        self.translations
+            .get(language)
+            .and_then(|text| text.tags.clone())
+            .unwrap_or_else(|| self.tags.clone())
+    }
+}
+
+/// Turns an id like `other_flower` into a name like "Other Flower".
+fn name_from_id(id: &str) -> String {
+    id.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 impl PartialOrd for PlantDefinition {
@@ -252,6 +436,50 @@ pub struct PlantDefinitions {
     pub definitions: Vec<PlantDefinition>,
     pub name_to_id: HashMap<String, usize>,
     pub assets: HashMap<String, TileAsset>,
+    /// Where each plant falls in the colour blind palette. Plants are given colours in order of
+    /// their ids, so they don't change as definitions are tweaked.
+    pub palette_index: HashMap<String, usize>,
+    /// The language plant names and descriptions are shown in, kept in step with the settings.
+    pub language: String,
+}
+
+impl PlantDefinitions {
+    pub fn get(&self, id: &str) -> Option<&PlantDefinition> {
+        self.name_to_id
+            .get(id)
+            .and_then(|index| self.definitions.get(*index))
+    }
+
+    /// The colour a plant is tinted with in the given palette.
+    pub fn color(&self, id: &str, palette: PlantPalette) -> Color {
+        let Some(definition) = self.get(id) else { return Color::WHITE; };
+        match palette {
+            PlantPalette::Standard => definition.color,
+            PlantPalette::ColourBlind => definition.accessible_color.unwrap_or_else(|| {
+                let index = self.palette_index.get(id).copied().unwrap_or_default();
+                OKABE_ITO[index % OKABE_ITO.len()]
+            }),
+        }
+    }
+
+    /// The name a plant is shown with, even if it has no definition.
+    pub fn name(&self, id: &str) -> String {
+        self.get(id)
+            .map(|definition| definition.name_in(&self.language))
+            .unwrap_or_else(|| name_from_id(id))
+    }
+
+    /// The names of several plants, as a list to read out.
+    pub fn names(&self, ids: &[String], strings: &Strings) -> String {
+        strings.list_or(ids.iter().map(|id| self.name(id)))
+    }
+
+    /// The symbol drawn over a plant when glyphs are turned on.
+    pub fn glyph(&self, id: &str) -> String {
+        self.get(id)
+            .and_then(|definition| definition.glyph.clone())
+            .unwrap_or_else(|| id.chars().next().unwrap_or('?').to_uppercase().to_string())
+    }
 }
 
 #[derive(Default, Debug, Clone, TypeUuid, Serialize, Deserialize)]
@@ -270,6 +498,10 @@ impl From<(PlantDefinitionsAsset, AssetServer)> for PlantDefinitions {
 
         p.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
+        let mut ids = p.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+
         Self {
             definitions: p.iter().map(|(_, p)| p.clone()).collect(),
             name_to_id: p
@@ -281,6 +513,12 @@ impl From<(PlantDefinitionsAsset, AssetServer)> for PlantDefinitions {
                 .iter()
                 .map(|(_, p)| (p.id.clone(), TileAsset(server.load(&p.asset), p.color)))
                 .collect(),
+            palette_index: ids
+                .into_iter()
+                .enumerate()
+                .map(|(index, id)| (id, index))
+                .collect(),
+            language: DEFAULT_LANGUAGE.to_string(),
         }
     }
 }
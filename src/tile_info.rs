@@ -0,0 +1,231 @@
+//! A tooltip describing the tile under the cursor - its ground, plant, fire and shrine - and
+//! which plants could live or spread there, with the reason when they can't.
+
+use belly::{core::ess::Styles, prelude::*};
+use bevy::{prelude::*, utils::HashMap, window::PrimaryWindow};
+use bevy_vector_shapes::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    assets::GameAssets,
+    colors::Palette,
+    control::{Action, Player},
+    locale::Strings,
+    pointer::world_to_tile,
+    states::AppState,
+    target::{Target, UsedTarget},
+    tile::{Ground, Plant, PlantDefinitions, Tile, TILE_WORLD_SIZE},
+    update_tiles::{spread_problem, survival_problem},
+};
+
+pub struct TileInfoPlugin;
+
+impl Plugin for TileInfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TileInfo>()
+            .add_system(toggle_keyboard_inspection.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                inspect_tile
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(toggle_keyboard_inspection),
+            )
+            .add_system(
+                draw_inspected_tile
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(inspect_tile),
+            )
+            .add_system(
+                setup_tooltip
+                    .in_base_set(CoreSet::PreUpdate)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_system(clear_tooltip.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+/// The tile being inspected and what the tooltip says about it.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TileInfo {
+    /// Inspects the tile the player is standing on, rather than the one under the cursor.
+    pub keyboard: bool,
+    pub tile: Option<Tile>,
+    pub lines: Vec<String>,
+}
+
+fn toggle_keyboard_inspection(actions: Res<ActionState<Action>>, mut info: ResMut<TileInfo>) {
+    if actions.just_pressed(Action::InspectTile) {
+        info.keyboard = !info.keyboard;
+    }
+}
+
+fn describe_tile(
+    tile: &Tile,
+    tiles: &HashMap<Tile, (&Ground, &Plant)>,
+    targets: &[&Target],
+    plants: &PlantDefinitions,
+    strings: &Strings,
+) -> Vec<String> {
+    let Some((ground, plant)) = tiles.get(tile) else { return vec![]; };
+
+    let mut lines = vec![strings.get(ground.label_key())];
+    lines.push(match plant {
+        Plant::Empty => strings.get("tile.no_plant"),
+        Plant::Plant(id) => strings.format("tile.growing", &[("plant", &plants.name(id))]),
+        Plant::Fire(1) => strings.get("tile.burning_last"),
+        Plant::Fire(remaining) => strings.format("tile.burning", &[("remaining", remaining)]),
+    });
+
+    for target in targets.iter().filter(|target| target.0 == *tile) {
+        lines.push(strings.format(
+            "tile.shrine",
+            &[
+                ("plant", &plants.name(&target.1)),
+                ("reward", &target.2.describe(strings)),
+            ],
+        ));
+    }
+
+    for definition in plants.definitions.iter() {
+        let name = plants.name(&definition.id);
+        let line = if matches!(plant, Plant::Plant(current) if *current == definition.id) {
+            match survival_problem(definition, ground, plant, tile, tiles) {
+                None => strings.format("tile.keeps_growing", &[("plant", &name)]),
+                Some(problem) => strings.format(
+                    "tile.will_die",
+                    &[
+                        ("plant", &name),
+                        ("problem", &problem.describe(plants, strings)),
+                    ],
+                ),
+            }
+        } else if let Some(problem) = survival_problem(definition, ground, plant, tile, tiles) {
+            strings.format(
+                "tile.cant_live",
+                &[
+                    ("plant", &name),
+                    ("problem", &problem.describe(plants, strings)),
+                ],
+            )
+        } else {
+            match spread_problem(definition, plant, ground, tile, tiles) {
+                None => strings.format("tile.will_spread", &[("plant", &name)]),
+                Some(problem) => strings.format(
+                    "tile.wont_spread",
+                    &[
+                        ("plant", &name),
+                        ("problem", &problem.describe(plants, strings)),
+                    ],
+                ),
+            }
+        };
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[allow(clippy::too_many_arguments)]
+fn inspect_tile(
+    mut info: ResMut<TileInfo>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    interactions: Query<&Interaction>,
+    players: Query<&Player>,
+    tiles: Query<(&Tile, &Ground, &Plant)>,
+    targets: Query<&Target, Without<UsedTarget>>,
+    plants: Res<PlantDefinitions>,
+    strings: Res<Strings>,
+) {
+    let hovered = || {
+        // The cards and buttons cover the tiles underneath them.
+        if interactions.iter().any(|i| *i != Interaction::None) {
+            return None;
+        }
+        let window = windows.get_single().ok()?;
+        let (camera, camera_transform) = cameras.get_single().ok()?;
+        let position = camera.viewport_to_world_2d(camera_transform, window.cursor_position()?)?;
+        Some(world_to_tile(position))
+    };
+    let tile = if info.keyboard {
+        players.iter().next().map(|p| Tile(p.0, p.1))
+    } else {
+        hovered()
+    };
+
+    let tiles = tiles
+        .iter()
+        .map(|(t, g, p)| (*t, (g, p)))
+        .collect::<HashMap<_, _>>();
+    let targets = targets.iter().collect::<Vec<_>>();
+    let lines = tile
+        .map(|tile| describe_tile(&tile, &tiles, &targets, &plants, &strings))
+        .unwrap_or_default();
+
+    // Only touch the resource when something changed, so the tooltip isn't rebuilt every frame.
+    if info.tile != tile || info.lines != lines {
+        info.tile = tile;
+        info.lines = lines;
+    }
+}
+
+fn draw_inspected_tile(mut painter: ShapePainter, info: Res<TileInfo>, palette: Res<Palette>) {
+    let Some(tile) = info.tile.filter(|_| !info.lines.is_empty()) else { return; };
+
+    painter.hollow = true;
+    painter.thickness = 3.;
+    painter.color = palette.pale;
+    let center = Vec2::new(tile.0 as f32, tile.1 as f32) * TILE_WORLD_SIZE;
+    painter.set_translation(center.extend(5.));
+    painter.rect(Vec2::splat(TILE_WORLD_SIZE));
+}
+
+#[derive(Component)]
+struct Tooltip;
+
+fn setup_tooltip(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut styles: ResMut<Styles>,
+    info: Res<TileInfo>,
+    strings: Res<Strings>,
+    query: Query<Entity, With<Tooltip>>,
+) {
+    if !info.is_changed() && !strings.is_changed() {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some((header, lines)) = info.lines.split_first() else { return; };
+    let header = header.clone();
+    let lines = lines.to_vec();
+
+    styles.insert(assets.ui_style.clone());
+    let ui = commands.spawn(Tooltip).id();
+    let root = strings.root_class("tile_info");
+
+    commands.add(eml! {
+        <div {ui} class={root}>
+            <span c:tile_info_header>{header}</span>
+            <for line in=lines>
+                <span c:tile_info_line>{line}</span>
+            </for>
+        </div>
+    });
+}
+
+fn clear_tooltip(
+    mut commands: Commands,
+    query: Query<Entity, With<Tooltip>>,
+    mut info: ResMut<TileInfo>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *info = TileInfo {
+        keyboard: info.keyboard,
+        ..default()
+    };
+}
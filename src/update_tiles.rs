@@ -1,29 +1,76 @@
-use std::time::Duration;
-
-use bevy::{prelude::*, time::common_conditions::on_timer, utils::HashMap};
+use bevy::{prelude::*, utils::HashMap};
 
 use crate::{
-    control::{AvailablePowers, Power, Seed, UsePower},
+    clock::{generation_due, tick_clock, SimulationClock},
+    control::{move_player, AvailablePowers, Power, Seed, UsePower},
+    generate_tiles::LevelLoaded,
+    locale::Strings,
+    replay::generation_ready,
     states::AppState,
-    tile::{Ground, Plant, PlantDefinition, PlantDefinitions, SpreadType, Tile, FIRE_DURATION},
+    tile::{
+        Ground, GroundList, Plant, PlantDefinition, PlantDefinitions, SpreadType, Tile,
+        FIRE_DURATION,
+    },
 };
 
 pub struct UpdateTilesPlugin;
 
 impl Plugin for UpdateTilesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(
-            update_tiles.run_if(
-                in_state(AppState::InGame).and_then(on_timer(Duration::from_secs_f32(0.5))),
-            ),
-        )
-        .add_system(use_powers.in_set(OnUpdate(AppState::InGame)));
+        app.init_resource::<Generation>()
+            .add_event::<PlantSpread>()
+            .add_event::<FireIgnited>()
+            .add_event::<PowerApplied>()
+            // A generation runs on the tiles as the player's actions this frame left them,
+            // so replays see the same order of events as the game they were recorded from.
+            .add_system(
+                update_tiles
+                    .after(tick_clock)
+                    .after(move_player)
+                    .after(use_powers)
+                    .run_if(
+                        in_state(AppState::InGame)
+                            .and_then(generation_due)
+                            .and_then(generation_ready),
+                    ),
+            )
+            .add_system(
+                use_powers
+                    .in_set(OnUpdate(AppState::InGame))
+                    .after(move_player),
+            )
+            .add_system(reset_generation.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(
+                reset_generation
+                    .run_if(in_state(AppState::InGame).and_then(on_event::<LevelLoaded>())),
+            );
     }
 }
 
-fn use_powers(
+/// How many times the simulation has run since the level was loaded.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Generation(pub usize);
+
+/// Sent when a plant grows onto a tile during a generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlantSpread(pub Tile, pub String);
+
+/// Sent when a tile catches fire during a generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FireIgnited(pub Tile);
+
+/// Sent when a power is used and actually changes something, unlike a [`UsePower`] that may be refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerApplied(pub Power, pub Tile);
+
+fn reset_generation(mut commands: Commands) {
+    commands.insert_resource(Generation::default());
+}
+
+pub fn use_powers(
     query: Query<(Entity, &Tile, &Plant, &Ground)>,
     mut use_power: EventReader<UsePower>,
+    mut applied: EventWriter<PowerApplied>,
     mut commands: Commands,
     mut powers: ResMut<AvailablePowers>,
     mut seed: ResMut<Seed>,
@@ -33,118 +80,177 @@ fn use_powers(
         .iter()
         .map(|(_, t, c, b)| (*t, (b, c)))
         .collect::<HashMap<_, _>>();
+    let entities = query
+        .iter()
+        .map(|(e, t, _, _)| (*t, e))
+        .collect::<HashMap<_, _>>();
 
     for UsePower(power, tile) in use_power.iter() {
-        if let Some((entity, t, plant, ground)) = query.iter().find(|(_, t, _, _)| **t == *tile) {
-            match power {
-                Power::Fertilize => {
-                    powers.adjust(power.clone(), -1);
-                    for (entity, tile, _, ground) in query.iter() {
-                        if tile.0.abs_diff(t.0) < 2 && tile.1.abs_diff(t.1) < 2 {
-                            match ground {
-                                Ground::Soil(false) => {
-                                    commands.entity(entity).insert(Ground::Soil(true));
-                                }
-                                Ground::Sand(false) => {
-                                    commands.entity(entity).insert(Ground::Sand(true));
-                                }
-                                Ground::Rock(false) => {
-                                    commands.entity(entity).insert(Ground::Rock(true));
-                                }
-                                _ => {}
-                            };
-                        }
-                    }
-                }
-                Power::Fire => {
-                    if !matches!(ground, Ground::Water)
-                        && !matches!(ground, Ground::Empty)
-                        && !matches!(plant, Plant::Empty)
-                    {
-                        commands.entity(entity).insert(Plant::Fire(FIRE_DURATION));
-                        powers.adjust(power.clone(), -1);
-                    }
-                }
-                Power::Seed => {
-                    if let Plant::Plant(p) = plant {
-                        info!("Getting Seed {p}");
-                        let Some(asset) = plants.name_to_id.get(p) else {continue;};
-                        info!("ID: {asset}");
-                        let Some(asset) = plants.definitions.get(*asset) else { continue;};
-                        info!("Asset: {asset:?}");
-
-                        seed.0 = Some((p.clone(), asset.asset.clone(), asset.color));
-                        powers.adjust(Power::Plant, 1);
-                        powers.adjust(power.clone(), -1);
-                    }
-                }
-                Power::Drain => {
-                    powers.adjust(power.clone(), -1);
-                    for (entity, tile, _, ground) in query.iter() {
-                        if tile.0.abs_diff(t.0) < 2 && tile.1.abs_diff(t.1) < 2 {
-                            match ground {
-                                Ground::Soil(true) => {
-                                    commands.entity(entity).insert(Ground::Soil(false));
-                                }
-                                Ground::Sand(true) => {
-                                    commands.entity(entity).insert(Ground::Sand(false));
-                                }
-                                Ground::Rock(true) => {
-                                    commands.entity(entity).insert(Ground::Rock(false));
-                                }
-                                _ => {}
-                            };
-                        }
-                    }
-                }
-                Power::Plant => {
-                    let Some((plant_id, _, _)) = &seed.0 else { continue; };
-                    let Some(plant_id) = plants.name_to_id.get(plant_id) else {continue;};
-                    let Some(plant_definition) = plants.definitions.get(*plant_id) else { continue;};
-                    if can_survive(plant_definition, ground, plant, tile, &tiles) {
-                        commands
-                            .entity(entity)
-                            .insert(Plant::Plant(plant_definition.id.clone()));
-                        powers.adjust(power.clone(), -1);
-                        seed.0 = None;
+        let seed_id = seed.0.as_ref().map(|(id, _)| id.as_str());
+        let Some(effect) = power_effect(power, tile, &tiles, seed_id, &plants) else { continue; };
+
+        powers.adjust(power.clone(), -1);
+        applied.send(PowerApplied(power.clone(), *tile));
+        if let Some(gained) = effect.gained {
+            powers.adjust(gained, 1);
+        }
+        if let Some(new_seed) = effect.seed {
+            seed.0 = new_seed.and_then(|id| {
+                let definition = plants
+                    .name_to_id
+                    .get(&id)
+                    .and_then(|i| plants.definitions.get(*i))?;
+                Some((id, definition.asset.clone()))
+            });
+        }
+
+        for (tile, ground, plant) in effect.tiles {
+            let (Some(entity), Some((old_ground, old_plant))) = (entities.get(&tile), tiles.get(&tile)) else { continue; };
+            if ground != **old_ground {
+                commands.entity(*entity).insert(ground);
+            }
+            if plant != **old_plant {
+                commands.entity(*entity).insert(plant);
+            }
+        }
+    }
+}
+
+/// What using a power on a tile changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PowerEffect {
+    pub tiles: Vec<(Tile, Ground, Plant)>,
+    /// The seed being carried afterwards, if the power changes it.
+    pub seed: Option<Option<String>>,
+    pub gained: Option<Power>,
+}
+
+/// Works out what a power would do to the tiles without touching the world,
+/// or `None` if it can't be used on that tile.
+pub fn power_effect(
+    power: &Power,
+    target: &Tile,
+    tiles: &HashMap<Tile, (&Ground, &Plant)>,
+    seed: Option<&str>,
+    plants: &PlantDefinitions,
+) -> Option<PowerEffect> {
+    let (ground, plant) = tiles.get(target)?;
+    let mut effect = PowerEffect::default();
+
+    match power {
+        Power::Fertilize | Power::Drain => {
+            let fertile = *power == Power::Fertilize;
+            for (tile, (ground, plant)) in tiles.iter() {
+                if tile.0.abs_diff(target.0) < 2 && tile.1.abs_diff(target.1) < 2 {
+                    let new_ground = match ground {
+                        Ground::Soil(_) => Ground::Soil(fertile),
+                        Ground::Sand(_) => Ground::Sand(fertile),
+                        Ground::Rock(_) => Ground::Rock(fertile),
+                        ground => **ground,
+                    };
+                    if new_ground != **ground {
+                        effect.tiles.push((*tile, new_ground, (*plant).clone()));
                     }
                 }
             }
         }
+        Power::Fire => {
+            if matches!(ground, Ground::Water | Ground::Empty) || matches!(plant, Plant::Empty) {
+                return None;
+            }
+            effect
+                .tiles
+                .push((*target, **ground, Plant::Fire(FIRE_DURATION)));
+        }
+        Power::Seed => {
+            let Plant::Plant(p) = plant else { return None; };
+            if !plants.name_to_id.contains_key(p) {
+                return None;
+            }
+            effect.seed = Some(Some(p.clone()));
+            effect.gained = Some(Power::Plant);
+        }
+        Power::Plant => {
+            let plant_id = plants.name_to_id.get(seed?)?;
+            let plant_definition = plants.definitions.get(*plant_id)?;
+            if !can_survive(plant_definition, ground, plant, target, tiles) {
+                return None;
+            }
+            effect.tiles.push((
+                *target,
+                **ground,
+                Plant::Plant(plant_definition.id.clone()),
+            ));
+            effect.seed = Some(None);
+        }
     }
+
+    Some(effect)
 }
 
-fn update_tiles(
+pub fn update_tiles(
     query: Query<(Entity, &Ground, &Plant, &Tile)>,
     mut commands: Commands,
     plants: Res<PlantDefinitions>,
+    mut generation: ResMut<Generation>,
+    mut clock: ResMut<SimulationClock>,
+    mut spread: EventWriter<PlantSpread>,
+    mut ignited: EventWriter<FireIgnited>,
 ) {
+    clock.bypass_change_detection().consume();
+    generation.0 += 1;
+
     let tiles = query
         .iter()
         .map(|(_, b, c, t)| (*t, (b, c)))
         .collect::<HashMap<_, _>>();
 
     for (entity, ground, plant, tile) in query.iter() {
-        let new_ground = update_backing(
-            ground,
-            plant,
-            tile,
-            &tiles,
-            &plants.definitions,
-            &plants.name_to_id,
-        );
-        let new_plant = update_plant(&new_ground, plant, tile, &tiles, &plants.definitions);
+        let (new_ground, new_plant) = update_tile(ground, plant, tile, &tiles, &plants);
 
         if new_ground != *ground {
             commands.entity(entity).insert(new_ground);
         }
 
         if new_plant != *plant {
+            match (plant, &new_plant) {
+                (Plant::Fire(_), _) => {}
+                (_, Plant::Fire(_)) => ignited.send(FireIgnited(*tile)),
+                (_, Plant::Plant(id)) => spread.send(PlantSpread(*tile, id.clone())),
+                _ => {}
+            }
             commands.entity(entity).insert(new_plant);
         }
     }
 }
 
+pub fn update_tile(
+    ground: &Ground,
+    plant: &Plant,
+    tile: &Tile,
+    tiles: &HashMap<Tile, (&Ground, &Plant)>,
+    plants: &PlantDefinitions,
+) -> (Ground, Plant) {
+    let new_ground = update_backing(
+        ground,
+        plant,
+        tile,
+        tiles,
+        &plants.definitions,
+        &plants.name_to_id,
+    );
+    let new_plant = update_plant(&new_ground, plant, tile, tiles, &plants.definitions);
+    (new_ground, new_plant)
+}
+
+/// Returns true if running another generation would leave every tile unchanged.
+pub fn is_settled(tiles: &HashMap<Tile, (&Ground, &Plant)>, plants: &PlantDefinitions) -> bool {
+    tiles.iter().all(|(tile, (ground, plant))| {
+        let (new_ground, new_plant) = update_tile(ground, plant, tile, tiles, plants);
+        new_ground == **ground && new_plant == **plant
+    })
+}
+
 fn can_survive(
     plant_definition: &PlantDefinition,
     ground: &Ground,
@@ -152,18 +258,88 @@ fn can_survive(
     tile: &Tile,
     tiles: &HashMap<Tile, (&Ground, &Plant)>,
 ) -> bool {
+    survival_problem(plant_definition, ground, plant, tile, tiles).is_none()
+}
+
+/// Why a plant can't live on, or won't spread onto, a tile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GrowthProblem<'a> {
+    Burning,
+    WrongGround(&'a GroundList),
+    MissingNeighbourGround(&'a GroundList),
+    MissingNeighbourPlant(&'a [String]),
+    Occupied,
+    TooFewNeighbours {
+        plant: &'a str,
+        needed: usize,
+        found: usize,
+    },
+    MissingSpreadNeighbour(&'a [String]),
+    OnlyPlanted,
+}
+
+impl GrowthProblem<'_> {
+    pub fn describe(&self, plants: &PlantDefinitions, strings: &Strings) -> String {
+        match self {
+            GrowthProblem::Burning => strings.get("problem.burning"),
+            GrowthProblem::WrongGround(grounds) => strings.format(
+                "problem.wrong_ground",
+                &[("grounds", &grounds.names(strings))],
+            ),
+            GrowthProblem::MissingNeighbourGround(grounds) => strings.format(
+                "problem.missing_neighbour",
+                &[("needs", &grounds.names(strings))],
+            ),
+            GrowthProblem::MissingNeighbourPlant(ids) => strings.format(
+                "problem.missing_neighbour",
+                &[("needs", &plants.names(ids, strings))],
+            ),
+            GrowthProblem::Occupied => strings.get("problem.occupied"),
+            GrowthProblem::TooFewNeighbours {
+                plant,
+                needed,
+                found,
+            } => strings.format(
+                "problem.too_few",
+                &[
+                    ("needed", needed),
+                    ("plant", &plants.name(plant)),
+                    ("found", found),
+                ],
+            ),
+            GrowthProblem::MissingSpreadNeighbour(ids) => strings.format(
+                "problem.missing_spread_neighbour",
+                &[("plants", &plants.names(ids, strings))],
+            ),
+            GrowthProblem::OnlyPlanted => strings.get("problem.only_planted"),
+        }
+    }
+}
+
+/// Why a plant can't live on a tile, or `None` if it can.
+pub fn survival_problem<'a>(
+    plant_definition: &'a PlantDefinition,
+    ground: &Ground,
+    plant: &Plant,
+    tile: &Tile,
+    tiles: &HashMap<Tile, (&Ground, &Plant)>,
+) -> Option<GrowthProblem<'a>> {
     if matches!(plant, Plant::Fire(_)) {
-        return false;
+        return Some(GrowthProblem::Burning);
     }
     if !plant_definition.allowed_grounds.0.contains(ground) {
-        return false;
+        return Some(GrowthProblem::WrongGround(
+            &plant_definition.allowed_grounds,
+        ));
     }
     if !plant_definition.required_neighbour_grounds.0.is_empty()
         && count_matching_neighbours(tile, tiles, |(g, _)| {
             plant_definition.required_neighbour_grounds.0.contains(*g)
         }) == 0
     {
-        return false;
+        return Some(GrowthProblem::MissingNeighbourGround(
+            &plant_definition.required_neighbour_grounds,
+        ));
     }
     if !plant_definition.required_neighbour_plants.is_empty()
         && count_matching_neighbours(tile, tiles, |(_, p)| {
@@ -174,10 +350,12 @@ fn can_survive(
             }
         }) == 0
     {
-        return false;
+        return Some(GrowthProblem::MissingNeighbourPlant(
+            &plant_definition.required_neighbour_plants,
+        ));
     }
 
-    true
+    None
 }
 
 fn can_spread(
@@ -187,47 +365,54 @@ fn can_spread(
     tile: &Tile,
     tiles: &HashMap<Tile, (&Ground, &Plant)>,
 ) -> bool {
-    if !can_survive(plant_definition, ground, plant, tile, tiles) {
-        return false;
+    spread_problem(plant_definition, plant, ground, tile, tiles).is_none()
+}
+
+/// Why a plant won't spread onto a tile, or `None` if it will.
+pub fn spread_problem<'a>(
+    plant_definition: &'a PlantDefinition,
+    plant: &Plant,
+    ground: &Ground,
+    tile: &Tile,
+    tiles: &HashMap<Tile, (&Ground, &Plant)>,
+) -> Option<GrowthProblem<'a>> {
+    if let Some(problem) = survival_problem(plant_definition, ground, plant, tile, tiles) {
+        return Some(problem);
     }
 
+    let too_few = |needed: usize| {
+        let found = count_matching_neighbours(tile, tiles, |(_, p)| {
+            if let Plant::Plant(p) = p {
+                *p == plant_definition.id
+            } else {
+                false
+            }
+        });
+        (found < needed).then_some(GrowthProblem::TooFewNeighbours {
+            plant: &plant_definition.id,
+            needed,
+            found,
+        })
+    };
+
     match &plant_definition.spread {
-        SpreadType::AdjacentEmpty(n) => {
-            *plant == Plant::Empty
-                && count_matching_neighbours(tile, tiles, |(_, p)| {
-                    if let Plant::Plant(p) = p {
-                        *p == plant_definition.id
-                    } else {
-                        false
-                    }
-                }) >= *n
-        }
-        SpreadType::AdjacentAggresive(n) => {
-            count_matching_neighbours(tile, tiles, |(_, p)| {
-                if let Plant::Plant(p) = p {
-                    *p == plant_definition.id
-                } else {
-                    false
-                }
-            }) >= *n
-        }
+        SpreadType::AdjacentEmpty(_) if *plant != Plant::Empty => Some(GrowthProblem::Occupied),
+        SpreadType::AdjacentEmpty(n) | SpreadType::AdjacentAggresive(n) => too_few(*n),
         SpreadType::AdjacentRequire(n, req) => {
-            count_matching_neighbours(tile, tiles, |(_, p)| {
+            if count_matching_neighbours(tile, tiles, |(_, p)| {
                 if let Plant::Plant(p) = p {
                     req.contains(p)
                 } else {
                     false
                 }
-            }) >= 1
-                && count_matching_neighbours(tile, tiles, |(_, p)| {
-                    if let Plant::Plant(p) = p {
-                        *p == plant_definition.id
-                    } else {
-                        false
-                    }
-                }) >= *n
+            }) == 0
+            {
+                Some(GrowthProblem::MissingSpreadNeighbour(req))
+            } else {
+                too_few(*n)
+            }
         }
-        _ => false,
+        SpreadType::Seeded | SpreadType::SeededRequire(_) => Some(GrowthProblem::OnlyPlanted),
     }
 }
 
@@ -297,7 +482,7 @@ fn update_backing(
     *ground
 }
 
-const NEIGHBOURHOOD: [(i8, i8); 8] = [
+pub const NEIGHBOURHOOD: [(i8, i8); 8] = [
     (-1, -1),
     (0, -1),
     (1, -1),